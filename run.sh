@@ -42,10 +42,12 @@ fi
 ##? Usage:
 ##?   run.sh (-h|--help)
 ##?   run.sh test <year> [<day> [<puzzle_number>]] [--implem=<lang>]
-##?   run.sh run <year> <day> [<puzzle_number>] [--implem=<lang>]
+##?   run.sh run <year> <day> [<puzzle_number>] [--implem=<lang>] [--render=<dir>] [--verbose]
 ##?
 ##? Options:
 ##?   --implem=<lang>  Implementation of the solution [default: rust].
+##?   --render=<dir>   Directory where the days dump images of their final state.
+##?   --verbose        Print details about how the answers were found.
 ##?
 ##? Examples:
 ##?   run.sh run 2021 12
@@ -59,6 +61,8 @@ implem_rust() {
   local year=$2
   local day=$3
   local number=$4
+  local render_dir=$5
+  local verbose=$6
 
   if [[ $is_test == "true" ]]
   then
@@ -70,8 +74,11 @@ implem_rust() {
   else
       local input
       input=$(get_input_with_cache "$year" "$day")
+      local run_args=()
+      if [[ $render_dir ]]; then run_args=(--render "$(realpath "$render_dir")"); fi
+      if [[ $verbose == "true" ]]; then run_args+=(--verbose); fi
       cd "$(dirname "$0")/rust"
-      echo "$input" | cargo run --release -- "$year" "$day" "$number"
+      echo "$input" | cargo run --release -- "$year" "$day" "$number" "${run_args[@]}"
   fi
 }
 
@@ -79,7 +86,7 @@ implem_rust() {
 case ${args["--implem"]} in
 
   "rust")
-  implem_rust ${args["test"]} ${args["<year>"]} ${args["<day>"]} ${args["<puzzle_number>"]}
+  implem_rust "${args["test"]}" "${args["<year>"]}" "${args["<day>"]}" "${args["<puzzle_number>"]}" "${args["--render"]}" "${args["--verbose"]}"
   ;;
 
   *)
@@ -0,0 +1,17 @@
+#![no_main]
+// Only the modules needed by day 13 are compiled, most of their items are not used here
+#![allow(unused)]
+
+use libfuzzer_sys::fuzz_target;
+
+// The main package has no library, so the modules are included from its sources
+#[path = "../../src/days/y2022/day_13.rs"]
+mod day_13;
+#[path = "../../src/parsing.rs"]
+mod parsing;
+#[path = "../../src/structs/mod.rs"]
+mod structs;
+#[path = "../../src/utils.rs"]
+mod utils;
+
+fuzz_target!(|data: &[u8]| day_13::check_parse_input_list(data));
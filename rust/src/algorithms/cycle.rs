@@ -0,0 +1,129 @@
+use std::{collections::HashMap, hash::Hash};
+
+use num::PrimInt;
+
+#[derive(Debug, Clone)]
+pub struct Trajectory<O> {
+    // Observation returned by each step, the first one being the result of step 1
+    pub observations: Vec<O>,
+    // After `start` steps, the states repeat every `length` steps
+    cycle: Option<(usize, usize)>,
+}
+
+impl<O> Trajectory<O> {
+    pub fn cycle_start(&self) -> Option<usize> {
+        self.cycle.map(|(start, _)| start)
+    }
+
+    pub fn cycle_length(&self) -> Option<usize> {
+        self.cycle.map(|(_, length)| length)
+    }
+
+    // Index of an observation which is the same as the one of step `step`, counted from 1
+    pub fn equivalent_step(&self, step: usize) -> Option<usize> {
+        if step <= self.observations.len() {
+            return Some(step);
+        }
+        let (start, length) = self.cycle?;
+        Some(start + (step - start - 1) % length + 1)
+    }
+
+    // Sum of the metric over the first `steps` steps
+    pub fn extrapolate<T>(&self, steps: usize, metric: impl Fn(&O) -> T) -> Option<T>
+    where
+        T: PrimInt,
+    {
+        let sum = |from: usize, to: usize| {
+            self.observations[from..to]
+                .iter()
+                .fold(T::zero(), |acc, o| acc + metric(o))
+        };
+
+        if steps <= self.observations.len() {
+            return Some(sum(0, steps));
+        }
+
+        let (start, length) = self.cycle?;
+        let cycles = T::from((steps - start) / length)?;
+        let remainder = (steps - start) % length;
+
+        Some(sum(0, start) + cycles * sum(start, start + length) + sum(start, start + remainder))
+    }
+}
+
+// Runs `step` on the state until a state key is seen twice, or `max_steps` steps were done
+pub fn find_cycle<S, K, O>(
+    mut state: S,
+    mut step: impl FnMut(&mut S) -> O,
+    key: impl Fn(&S) -> K,
+    max_steps: usize,
+) -> Trajectory<O>
+where
+    K: Eq + Hash,
+{
+    let mut seen = HashMap::from([(key(&state), 0)]);
+    let mut observations = vec![];
+
+    while observations.len() < max_steps {
+        observations.push(step(&mut state));
+        let step_number = observations.len();
+        if let Some(start) = seen.insert(key(&state), step_number) {
+            return Trajectory {
+                observations,
+                cycle: Some((start, step_number - start)),
+            };
+        }
+    }
+
+    Trajectory {
+        observations,
+        cycle: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_cycle() {
+        // 3 -> 10 -> 5 -> 16 -> 8 -> 4 -> 2 -> 1 -> 4 -> ...
+        let collatz = |n: &mut u64| {
+            *n = match n.is_multiple_of(2) {
+                true => *n / 2,
+                false => 3 * *n + 1,
+            };
+            *n
+        };
+        let trajectory = find_cycle(3u64, collatz, |&n| n, 100);
+
+        assert_eq!(trajectory.cycle_start(), Some(5));
+        assert_eq!(trajectory.cycle_length(), Some(3));
+        assert_eq!(trajectory.observations, vec![10, 5, 16, 8, 4, 2, 1, 4]);
+        assert_eq!(trajectory.equivalent_step(9), Some(6));
+        assert_eq!(trajectory.equivalent_step(12), Some(6));
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let counter = |n: &mut u64| {
+            *n = (*n + 1) % 4;
+            *n
+        };
+        let trajectory = find_cycle(0u64, counter, |&n| n, 100);
+
+        // 1 + 2 + 3 + 0 + 1 + 2 + 3 + 0 + 1 + 2
+        assert_eq!(trajectory.extrapolate(10, |&o| o), Some(15));
+        assert_eq!(trajectory.extrapolate(2, |&o| o), Some(3));
+        assert_eq!(trajectory.extrapolate(1_000_001, |&o| o), Some(1_500_001));
+    }
+
+    #[test]
+    fn test_no_cycle_within_limit() {
+        let trajectory = find_cycle(0u64, |n| *n += 1, |&n| n, 10);
+
+        assert_eq!(trajectory.cycle_length(), None);
+        assert_eq!(trajectory.extrapolate(10, |_| 1u32), Some(10));
+        assert_eq!(trajectory.extrapolate(11, |_| 1u32), None);
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+use num::Zero;
+
+use super::search::{Graph, Neighbours};
+
+// Directed graph with weighted edges, nodes are referred to by their insertion index
+#[derive(Debug, Clone)]
+pub struct WeightedGraph<N, C> {
+    nodes: Vec<N>,
+    indices: HashMap<N, usize>,
+    edges: Vec<Vec<(usize, C)>>,
+}
+
+impl<N, C> Default for WeightedGraph<N, C> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![],
+            indices: HashMap::new(),
+            edges: vec![],
+        }
+    }
+}
+
+// Distances between every pair of nodes, None when there is no path
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DistanceMatrix<C> {
+    size: usize,
+    distances: Vec<Option<C>>,
+}
+
+impl<C> DistanceMatrix<C>
+where
+    C: Copy,
+{
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            distances: vec![None; size * size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, from: usize, to: usize) -> Option<C> {
+        self.distances[from * self.size + to]
+    }
+
+    fn set(&mut self, from: usize, to: usize, distance: C) {
+        self.distances[from * self.size + to] = Some(distance);
+    }
+}
+
+impl<N, C> WeightedGraph<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> &N {
+        &self.nodes[index]
+    }
+
+    pub fn index_of(&self, node: &N) -> Option<usize> {
+        self.indices.get(node).copied()
+    }
+
+    // Returns the index of the node, adding it if it is not in the graph yet
+    pub fn add_node(&mut self, node: N) -> usize {
+        if let Some(&index) = self.indices.get(&node) {
+            return index;
+        }
+        self.nodes.push(node.clone());
+        self.edges.push(vec![]);
+        self.indices.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: N, to: N, cost: C) {
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+        self.edges[from].push((to, cost));
+    }
+
+    pub fn edges(&self, from: usize) -> &[(usize, C)] {
+        &self.edges[from]
+    }
+
+    pub fn floyd_warshall(&self) -> DistanceMatrix<C> {
+        let size = self.len();
+        let mut matrix = DistanceMatrix::new(size);
+        for (from, edges) in self.edges.iter().enumerate() {
+            matrix.set(from, from, C::zero());
+            for &(to, cost) in edges {
+                if matrix.get(from, to).is_none_or(|d| cost < d) {
+                    matrix.set(from, to, cost);
+                }
+            }
+        }
+
+        for k in 0..size {
+            for i in 0..size {
+                let Some(to_k) = matrix.get(i, k) else {
+                    continue;
+                };
+                for j in 0..size {
+                    let Some(from_k) = matrix.get(k, j) else {
+                        continue;
+                    };
+                    let distance = to_k + from_k;
+                    if matrix.get(i, j).is_none_or(|d| distance < d) {
+                        matrix.set(i, j, distance);
+                    }
+                }
+            }
+        }
+
+        matrix
+    }
+
+    // Keeps only the nodes selected by `keep`, with an edge for each path between them
+    pub fn compress(&self, keep: impl Fn(&N) -> bool) -> Self {
+        let distances = self.floyd_warshall();
+        let kept: Vec<_> = (0..self.len()).filter(|&i| keep(&self.nodes[i])).collect();
+
+        let mut graph = Self::new();
+        for &i in &kept {
+            graph.add_node(self.nodes[i].clone());
+        }
+        for &from in &kept {
+            for &to in kept.iter().filter(|&&to| to != from) {
+                if let Some(distance) = distances.get(from, to) {
+                    graph.add_edge(self.nodes[from].clone(), self.nodes[to].clone(), distance);
+                }
+            }
+        }
+        graph
+    }
+}
+
+impl<N, C> Neighbours for WeightedGraph<N, C>
+where
+    N: Clone + Eq + Hash,
+{
+    type Node = N;
+
+    fn neighbours(&self, node: &N) -> impl Iterator<Item = N> {
+        let edges = match self.indices.get(node) {
+            Some(&index) => self.edges[index].as_slice(),
+            None => &[],
+        };
+        edges.iter().map(|(to, _)| self.nodes[*to].clone())
+    }
+}
+
+impl<N, C> Graph for WeightedGraph<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+{
+    type Cost = C;
+
+    fn cost(&self, from: &N, to: &N) -> C {
+        let to = self.indices[to];
+        self.edges[self.indices[from]]
+            .iter()
+            .filter(|(n, _)| *n == to)
+            .map(|(_, cost)| *cost)
+            .min()
+            .expect("No edge between the nodes")
+    }
+}
+
+// Number of steps from `start` to every reachable node
+pub fn bfs_distances<G>(graph: &G, start: G::Node) -> HashMap<G::Node, usize>
+where
+    G: Neighbours,
+{
+    let mut distances = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[&node];
+        for neighbour in graph.neighbours(&node) {
+            if !distances.contains_key(&neighbour) {
+                distances.insert(neighbour.clone(), distance + 1);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    distances
+}
+
+// Repeated BFS, cheaper than Floyd-Warshall on sparse graphs with unit edges
+pub fn all_pairs_bfs<G>(
+    graph: &G,
+    sources: impl IntoIterator<Item = G::Node>,
+) -> HashMap<(G::Node, G::Node), usize>
+where
+    G: Neighbours,
+{
+    sources
+        .into_iter()
+        .flat_map(|source| {
+            bfs_distances(graph, source.clone())
+                .into_iter()
+                .map(move |(to, distance)| ((source.clone(), to), distance))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a -> b -> c -> d, with a shortcut a -> c
+    fn get_graph() -> WeightedGraph<char, u32> {
+        let mut graph = WeightedGraph::new();
+        graph.add_edge('a', 'b', 1);
+        graph.add_edge('b', 'c', 2);
+        graph.add_edge('c', 'd', 4);
+        graph.add_edge('a', 'c', 5);
+        graph
+    }
+
+    #[test]
+    fn test_floyd_warshall() {
+        let graph = get_graph();
+        let distances = graph.floyd_warshall();
+        let index = |c| graph.index_of(&c).unwrap();
+
+        assert_eq!(distances.get(index('a'), index('c')), Some(3));
+        assert_eq!(distances.get(index('a'), index('d')), Some(7));
+        assert_eq!(distances.get(index('d'), index('a')), None);
+        assert_eq!(distances.get(index('b'), index('b')), Some(0));
+    }
+
+    #[test]
+    fn test_all_pairs_bfs() {
+        let graph = get_graph();
+        let distances = all_pairs_bfs(&graph, ['a', 'b']);
+
+        assert_eq!(distances[&('a', 'c')], 1);
+        assert_eq!(distances[&('a', 'd')], 2);
+        assert_eq!(distances[&('b', 'd')], 2);
+        assert!(!distances.contains_key(&('b', 'a')));
+    }
+
+    #[test]
+    fn test_compress() {
+        let graph = get_graph().compress(|&c| c != 'c');
+
+        assert_eq!(graph.len(), 3);
+        assert_eq!(graph.cost(&'a', &'d'), 7);
+        assert_eq!(graph.neighbours(&'b').collect::<Vec<_>>(), vec!['d']);
+    }
+}
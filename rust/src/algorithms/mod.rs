@@ -0,0 +1,8 @@
+// Generic algorithms shared by the puzzles, each day only needs part of them
+#![allow(dead_code)]
+
+pub mod cycle;
+pub mod graph;
+pub mod numtheory;
+pub mod search;
+pub mod simulation;
@@ -0,0 +1,233 @@
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use num::{Integer, Signed};
+
+pub fn gcd_all<T>(values: impl IntoIterator<Item = T>) -> T
+where
+    T: Integer,
+{
+    values.into_iter().fold(T::zero(), |acc, v| acc.gcd(&v))
+}
+
+// The lcm of an empty set is 1
+pub fn lcm_all<T>(values: impl IntoIterator<Item = T>) -> T
+where
+    T: Integer,
+{
+    values.into_iter().fold(T::one(), |acc, v| acc.lcm(&v))
+}
+
+// Returns (g, x, y) such that a * x + b * y = g = gcd(a, b)
+pub fn extended_gcd<T>(a: T, b: T) -> (T, T, T)
+where
+    T: Integer + Signed + Copy,
+{
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::one(), T::zero());
+    let (mut old_y, mut y) = (T::zero(), T::one());
+
+    while !r.is_zero() {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+
+    match old_r.is_negative() {
+        true => (-old_r, -old_x, -old_y),
+        false => (old_r, old_x, old_y),
+    }
+}
+
+// Inverse of `a` modulo `modulus`, if `a` and `modulus` are coprime
+pub fn mod_inverse<T>(a: T, modulus: T) -> Option<T>
+where
+    T: Integer + Signed + Copy,
+{
+    let (g, x, _) = extended_gcd(a.mod_floor(&modulus), modulus);
+    match g.is_one() {
+        true => Some(x.mod_floor(&modulus)),
+        false => None,
+    }
+}
+
+pub fn mod_add(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 + b as u128) % modulus as u128) as u64
+}
+
+pub fn mod_mul(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 * b as u128) % modulus as u128) as u64
+}
+
+pub fn mod_pow(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    let mut base = base % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mod_mul(result, base, modulus);
+        }
+        base = mod_mul(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+// Solves x = r_i (mod m_i) for every (r_i, m_i), returning (x, lcm of the m_i).
+// The moduli do not need to be coprime, in which case None is returned if the system
+// has no solution.
+pub fn crt(congruences: impl IntoIterator<Item = (i64, i64)>) -> Option<(i64, i64)> {
+    congruences
+        .into_iter()
+        .try_fold((0i64, 1i64), |(r1, m1), (r2, m2)| {
+            let (g, p, _) = extended_gcd(m1 as i128, m2 as i128);
+            let difference = r2 as i128 - r1 as i128;
+            if difference % g != 0 {
+                return None;
+            }
+            let lcm = m1 as i128 / g * m2 as i128;
+            let step = (difference / g * p).mod_floor(&(m2 as i128 / g));
+            let x = (r1 as i128 + m1 as i128 * step).mod_floor(&lcm);
+            Some((i64::try_from(x).ok()?, i64::try_from(lcm).ok()?))
+        })
+}
+
+// Integer modulo M, all operations staying in [0, M)
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Mod<const M: u64>(u64);
+
+impl<const M: u64> Mod<M> {
+    pub fn new(value: u64) -> Self {
+        Self(value % M)
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    pub fn pow(self, exponent: u64) -> Self {
+        Self(mod_pow(self.0, exponent, M))
+    }
+
+    pub fn inverse(self) -> Option<Self> {
+        let inverse = mod_inverse(self.0 as i128, M as i128)?;
+        Some(Self(inverse as u64))
+    }
+}
+
+impl<const M: u64> From<u64> for Mod<M> {
+    fn from(value: u64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<const M: u64> Display for Mod<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const M: u64> Add for Mod<M> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(mod_add(self.0, rhs.0, M))
+    }
+}
+
+impl<const M: u64> AddAssign for Mod<M> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const M: u64> Sub for Mod<M> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl<const M: u64> SubAssign for Mod<M> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const M: u64> Mul for Mod<M> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self(mod_mul(self.0, rhs.0, M))
+    }
+}
+
+impl<const M: u64> MulAssign for Mod<M> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl<const M: u64> Neg for Mod<M> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self((M - self.0) % M)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd_all([12u64, 18, 30]), 6);
+        assert_eq!(lcm_all([23u64, 19, 13, 17]), 96577);
+        assert_eq!(lcm_all(Vec::<u64>::new()), 1);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let (g, x, y) = extended_gcd(240i64, 46);
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+        assert_eq!(mod_inverse(3i64, 11), Some(4));
+        assert_eq!(mod_inverse(4i64, 8), None);
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_add(u64::MAX - 1, 3, u64::MAX), 2);
+        assert_eq!(mod_mul(u64::MAX - 1, u64::MAX - 1, u64::MAX), 1);
+        assert_eq!(mod_pow(4, 13, 497), 445);
+        assert_eq!(mod_pow(u64::MAX - 1, 2, u64::MAX), 1);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // Non coprime moduli
+        assert_eq!(crt([(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt([(1, 4), (2, 6)]), None);
+    }
+
+    #[test]
+    fn test_mod_type() {
+        type M = Mod<1_000_000_007>;
+        let a = M::new(1_000_000_006);
+        assert_eq!(a + M::new(2), M::new(1));
+        assert_eq!(M::new(1) - M::new(2), a);
+        assert_eq!(a * a, M::new(1));
+        assert_eq!(M::new(2).pow(10), M::new(1024));
+        assert_eq!(M::new(3).inverse().unwrap() * M::new(3), M::new(1));
+
+        // Products are computed on 128 bits
+        type L = Mod<{ u64::MAX }>;
+        assert_eq!(L::new(u64::MAX - 1) * L::new(u64::MAX - 1), L::new(1));
+        assert_eq!(L::new(u64::MAX - 1) + L::new(3), L::new(2));
+    }
+}
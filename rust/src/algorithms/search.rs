@@ -0,0 +1,235 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+};
+
+use num::Zero;
+
+pub trait Neighbours {
+    type Node: Clone + Eq + Hash;
+
+    fn neighbours(&self, node: &Self::Node) -> impl Iterator<Item = Self::Node>;
+}
+
+// Weighted graph, the cost is the one of the edge going from `from` to its neighbour `to`
+pub trait Graph: Neighbours {
+    type Cost: Copy + Ord + Zero;
+
+    fn cost(&self, from: &Self::Node, to: &Self::Node) -> Self::Cost;
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult<N, C> {
+    pub goal: N,
+    pub distance: C,
+    parents: HashMap<N, N>,
+}
+
+impl<N, C> SearchResult<N, C>
+where
+    N: Clone + Eq + Hash,
+{
+    // Nodes from the start to the goal, both included
+    pub fn path(&self) -> Vec<N> {
+        let mut path = vec![self.goal.clone()];
+        while let Some(parent) = self.parents.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        path
+    }
+}
+
+pub fn bfs<G>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<SearchResult<G::Node, usize>>
+where
+    G: Neighbours,
+{
+    let mut distances = HashMap::new();
+    let mut parents = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for start in starts {
+        if distances.insert(start.clone(), 0).is_none() {
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[&node];
+        if is_goal(&node) {
+            return Some(SearchResult {
+                goal: node,
+                distance,
+                parents,
+            });
+        }
+        for neighbour in graph.neighbours(&node) {
+            if distances.contains_key(&neighbour) {
+                continue;
+            }
+            distances.insert(neighbour.clone(), distance + 1);
+            parents.insert(neighbour.clone(), node.clone());
+            queue.push_back(neighbour);
+        }
+    }
+
+    None
+}
+
+struct QueueItem<N, C> {
+    estimate: C,
+    node: N,
+}
+
+impl<N, C: Ord> PartialEq for QueueItem<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl<N, C: Ord> Eq for QueueItem<N, C> {}
+
+impl<N, C: Ord> PartialOrd for QueueItem<N, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Reversed, so that the binary heap pops the lowest estimate first
+impl<N, C: Ord> Ord for QueueItem<N, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.cmp(&self.estimate)
+    }
+}
+
+// The heuristic must never overestimate the remaining cost to a goal
+pub fn astar<G>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+    heuristic: impl Fn(&G::Node) -> G::Cost,
+) -> Option<SearchResult<G::Node, G::Cost>>
+where
+    G: Graph,
+{
+    let mut distances: HashMap<G::Node, G::Cost> = HashMap::new();
+    let mut parents = HashMap::new();
+    let mut queue = BinaryHeap::new();
+
+    for start in starts {
+        distances.insert(start.clone(), G::Cost::zero());
+        queue.push(QueueItem {
+            estimate: heuristic(&start),
+            node: start,
+        });
+    }
+
+    while let Some(QueueItem { estimate, node }) = queue.pop() {
+        let distance = distances[&node];
+        if estimate > distance + heuristic(&node) {
+            // Outdated item, the node was reached by a shorter path since
+            continue;
+        }
+        if is_goal(&node) {
+            return Some(SearchResult {
+                goal: node,
+                distance,
+                parents,
+            });
+        }
+        for neighbour in graph.neighbours(&node) {
+            let new_distance = distance + graph.cost(&node, &neighbour);
+            if distances
+                .get(&neighbour)
+                .is_some_and(|&known| known <= new_distance)
+            {
+                continue;
+            }
+            distances.insert(neighbour.clone(), new_distance);
+            parents.insert(neighbour.clone(), node.clone());
+            queue.push(QueueItem {
+                estimate: new_distance + heuristic(&neighbour),
+                node: neighbour,
+            });
+        }
+    }
+
+    None
+}
+
+pub fn dijkstra<G>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<SearchResult<G::Node, G::Cost>>
+where
+    G: Graph,
+{
+    astar(graph, starts, is_goal, |_| G::Cost::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Weighted directed graph given as an adjacency list
+    struct AdjacencyList(Vec<Vec<(usize, u32)>>);
+
+    impl Neighbours for AdjacencyList {
+        type Node = usize;
+
+        fn neighbours(&self, node: &usize) -> impl Iterator<Item = usize> {
+            self.0[*node].iter().map(|&(to, _)| to)
+        }
+    }
+
+    impl Graph for AdjacencyList {
+        type Cost = u32;
+
+        fn cost(&self, from: &usize, to: &usize) -> u32 {
+            self.0[*from].iter().find(|&&(n, _)| n == *to).unwrap().1
+        }
+    }
+
+    fn graph() -> AdjacencyList {
+        AdjacencyList(vec![
+            vec![(1, 7), (2, 1)],
+            vec![(4, 1)],
+            vec![(3, 2)],
+            vec![(1, 1), (4, 8)],
+            vec![],
+            vec![(4, 1)],
+        ])
+    }
+
+    #[test]
+    fn test_bfs() {
+        let result = bfs(&graph(), [0], |&n| n == 4).unwrap();
+        assert_eq!(result.distance, 2);
+        assert_eq!(result.path(), vec![0, 1, 4]);
+
+        let result = bfs(&graph(), [0, 5], |&n| n == 4).unwrap();
+        assert_eq!(result.path(), vec![5, 4]);
+
+        assert!(bfs(&graph(), [4], |&n| n == 0).is_none());
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let result = dijkstra(&graph(), [0], |&n| n == 4).unwrap();
+        assert_eq!(result.distance, 5);
+        assert_eq!(result.path(), vec![0, 2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn test_astar() {
+        let heuristic = |&n: &usize| if n == 4 { 0 } else { 1 };
+        let result = astar(&graph(), [0], |&n| n == 4, heuristic).unwrap();
+        assert_eq!(result.distance, 5);
+    }
+}
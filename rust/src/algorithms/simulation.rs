@@ -0,0 +1,233 @@
+use std::hash::Hash;
+
+use super::cycle::{find_cycle, Trajectory};
+
+pub trait Simulation {
+    // What can be seen from the outside after each step
+    type Observation;
+
+    fn step(&mut self) -> Self::Observation;
+
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+// Runs a simulation, optionally keeping all the observations made along the way
+#[derive(Debug, Clone)]
+pub struct Driver<S>
+where
+    S: Simulation,
+{
+    simulation: S,
+    steps: usize,
+    max_steps: Option<usize>,
+    record_trace: bool,
+    // Only the last observation is kept when the trace is not recorded
+    observations: Vec<S::Observation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot<S> {
+    simulation: S,
+    steps: usize,
+    trace_length: usize,
+}
+
+impl<S> Driver<S>
+where
+    S: Simulation,
+{
+    pub fn new(simulation: S) -> Self {
+        Self {
+            simulation,
+            steps: 0,
+            max_steps: None,
+            record_trace: false,
+            observations: vec![],
+        }
+    }
+
+    pub fn with_trace(mut self) -> Self {
+        self.record_trace = true;
+        self
+    }
+
+    // No step is made once `steps` steps were done
+    pub fn stop_after(mut self, steps: usize) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    pub fn simulation(&self) -> &S {
+        &self.simulation
+    }
+
+    pub fn into_simulation(self) -> S {
+        self.simulation
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn trace(&self) -> &[S::Observation] {
+        match self.record_trace {
+            true => &self.observations,
+            false => &[],
+        }
+    }
+
+    pub fn last_observation(&self) -> Option<&S::Observation> {
+        self.observations.last()
+    }
+
+    pub fn can_step(&self) -> bool {
+        !self.simulation.is_finished() && self.max_steps.is_none_or(|max| self.steps < max)
+    }
+
+    pub fn step(&mut self) -> Option<&S::Observation> {
+        if !self.can_step() {
+            return None;
+        }
+        let observation = self.simulation.step();
+        if !self.record_trace {
+            self.observations.clear();
+        }
+        self.observations.push(observation);
+        self.steps += 1;
+        self.observations.last()
+    }
+
+    // Returns the number of steps actually done
+    pub fn run(&mut self, steps: usize) -> usize {
+        let start = self.steps;
+        while self.steps - start < steps && self.step().is_some() {}
+        self.steps - start
+    }
+
+    pub fn run_to_end(&mut self) -> usize {
+        self.run(usize::MAX)
+    }
+
+    // Steps until the predicate holds for the simulation and the last observation,
+    // returns false if the simulation stopped before
+    pub fn run_until(&mut self, mut predicate: impl FnMut(&S, &S::Observation) -> bool) -> bool {
+        while self.step().is_some() {
+            if predicate(&self.simulation, self.observations.last().unwrap()) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl<S> Driver<S>
+where
+    S: Simulation + Clone,
+{
+    pub fn snapshot(&self) -> Snapshot<S> {
+        Snapshot {
+            simulation: self.simulation.clone(),
+            steps: self.steps,
+            trace_length: self.observations.len(),
+        }
+    }
+
+    // Observations made after the snapshot are removed from the trace
+    pub fn restore(&mut self, snapshot: Snapshot<S>) {
+        self.simulation = snapshot.simulation;
+        self.steps = snapshot.steps;
+        self.observations.truncate(snapshot.trace_length);
+    }
+}
+
+// The simulation is run until its key repeats, see `find_cycle`
+pub fn simulation_cycle<S, K>(
+    simulation: S,
+    key: impl Fn(&S) -> K,
+    max_steps: usize,
+) -> Trajectory<S::Observation>
+where
+    S: Simulation,
+    K: Eq + Hash,
+{
+    find_cycle(simulation, S::step, key, max_steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts down from a value, observing the value before each decrement
+    #[derive(Debug, Clone)]
+    struct Countdown(u32);
+
+    impl Simulation for Countdown {
+        type Observation = u32;
+
+        fn step(&mut self) -> u32 {
+            self.0 -= 1;
+            self.0 + 1
+        }
+
+        fn is_finished(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    #[test]
+    fn test_run_and_trace() {
+        let mut driver = Driver::new(Countdown(5)).with_trace();
+        assert_eq!(driver.run(2), 2);
+        assert!(driver.run_until(|s, _| s.0 == 1));
+        assert_eq!(driver.trace(), &[5, 4, 3, 2]);
+        assert_eq!(driver.run_to_end(), 1);
+        assert_eq!(driver.step(), None);
+        assert!(!driver.run_until(|_, _| true));
+
+        let mut driver = Driver::new(Countdown(5));
+        assert_eq!(driver.run_to_end(), 5);
+        assert_eq!(driver.trace(), &[]);
+        assert_eq!(driver.last_observation(), Some(&1));
+    }
+
+    #[test]
+    fn test_stop_after() {
+        let mut driver = Driver::new(Countdown(100)).stop_after(10);
+        assert_eq!(driver.run_to_end(), 10);
+        assert_eq!(driver.simulation().0, 90);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut driver = Driver::new(Countdown(10)).with_trace();
+        driver.run(3);
+        let snapshot = driver.snapshot();
+        driver.run(4);
+        assert_eq!(driver.simulation().0, 3);
+
+        driver.restore(snapshot);
+        assert_eq!(driver.steps(), 3);
+        assert_eq!(driver.simulation().0, 7);
+        assert_eq!(driver.trace(), &[10, 9, 8]);
+    }
+
+    #[test]
+    fn test_simulation_cycle() {
+        #[derive(Debug, Clone)]
+        struct Clock(u32);
+
+        impl Simulation for Clock {
+            type Observation = u32;
+
+            fn step(&mut self) -> u32 {
+                self.0 = (self.0 + 1) % 12;
+                self.0
+            }
+        }
+
+        let trajectory = simulation_cycle(Clock(3), |c| c.0, 100);
+        assert_eq!(trajectory.cycle_length(), Some(12));
+    }
+}
@@ -1,27 +1,34 @@
 use core::str;
 use std::error::Error;
 
-enum Movement {
-    Forward(u32),
-    Down(u32),
-    Up(u32),
+use crate::structs::geometry::{Direction4, Vector2};
+
+type Vector = Vector2<i32>;
+
+struct Movement {
+    direction: Direction4,
+    distance: i32,
 }
 
 struct Coord {
-    distance: u32,
-    depth: u32,
-    aim: u32,
+    distance: i32,
+    depth: i32,
+    aim: i32,
 }
 
 fn parse_line(line: &str) -> Result<Movement, Box<dyn Error>> {
-    let (direction, distance) = line.split_once(" ").ok_or("Cannot split line")?;
+    let (direction, distance) = line.split_once(' ').ok_or("Cannot split line")?;
     let distance = str::parse(distance)?;
-    match direction {
-        "forward" => Ok(Movement::Forward(distance)),
-        "down" => Ok(Movement::Down(distance)),
-        "up" => Ok(Movement::Up(distance)),
+    let direction = match direction {
+        "forward" => Direction4::Right,
+        "down" => Direction4::Down,
+        "up" => Direction4::Up,
         string => Err(format!("Unknown direction {string}"))?,
-    }
+    };
+    Ok(Movement {
+        direction,
+        distance,
+    })
 }
 
 fn load_input(input: &str) -> Vec<Movement> {
@@ -33,32 +40,14 @@ fn load_input(input: &str) -> Vec<Movement> {
 }
 
 pub fn puzzle_1(input: &str) -> String {
-    let coords = load_input(input).iter().fold(
-        Coord {
-            distance: 0,
-            depth: 0,
-            aim: 0,
-        },
-        |res, movement| match movement {
-            Movement::Forward(d) => Coord {
-                distance: res.distance + d,
-                depth: res.depth,
-                aim: res.aim,
-            },
-            Movement::Down(d) => Coord {
-                distance: res.distance,
-                depth: res.depth + d,
-                aim: res.aim,
-            },
-            Movement::Up(d) => Coord {
-                distance: res.distance,
-                depth: res.depth - d,
-                aim: res.aim,
-            },
-        },
-    );
+    let position = load_input(input)
+        .iter()
+        .fold(Vector::zero(), |position, movement| {
+            position + movement.direction.to_vector() * movement.distance
+        });
 
-    { coords.distance * coords.depth }.to_string()
+    // Depth grows when going down
+    { position.x * -position.y }.to_string()
 }
 
 pub fn puzzle_2(input: &str) -> String {
@@ -68,22 +57,28 @@ pub fn puzzle_2(input: &str) -> String {
             depth: 0,
             aim: 0,
         },
-        |res, movement| match movement {
-            Movement::Forward(d) => Coord {
-                distance: res.distance + d,
-                depth: res.depth + d * res.aim,
-                aim: res.aim,
-            },
-            Movement::Down(d) => Coord {
-                distance: res.distance,
-                depth: res.depth,
-                aim: res.aim + d,
-            },
-            Movement::Up(d) => Coord {
-                distance: res.distance,
-                depth: res.depth,
-                aim: res.aim - d,
-            },
+        |res, movement| {
+            let d = movement.distance;
+            match movement.direction {
+                Direction4::Right => Coord {
+                    distance: res.distance + d,
+                    depth: res.depth + d * res.aim,
+                    aim: res.aim,
+                },
+                Direction4::Left => Coord {
+                    distance: res.distance - d,
+                    depth: res.depth - d * res.aim,
+                    aim: res.aim,
+                },
+                Direction4::Down => Coord {
+                    aim: res.aim + d,
+                    ..res
+                },
+                Direction4::Up => Coord {
+                    aim: res.aim - d,
+                    ..res
+                },
+            }
         },
     );
 
@@ -14,7 +14,7 @@ fn parse_input(input: &str) -> (u16, Vec<u16>) {
     )
 }
 
-fn get_most_common_nth_bit(bits: &Vec<u16>, k: u16) -> u16 {
+fn get_most_common_nth_bit(bits: &[u16], k: u16) -> u16 {
     let counts = bits.iter().map(|&n| (n & (1 << k)) >> k).counts();
     if counts.get(&0).unwrap_or(&0) > counts.get(&1).unwrap_or(&1) {
         0
@@ -23,42 +23,42 @@ fn get_most_common_nth_bit(bits: &Vec<u16>, k: u16) -> u16 {
     }
 }
 
-fn get_least_common_nth_bit(bits: &Vec<u16>, k: u16) -> u16 {
+fn get_least_common_nth_bit(bits: &[u16], k: u16) -> u16 {
     get_most_common_nth_bit(bits, k) ^ 1
 }
 
-fn filter_on_most_common_nth_bit(bits: &Vec<u16>, k: u16) -> Vec<u16> {
+fn filter_on_most_common_nth_bit(bits: &[u16], k: u16) -> Vec<u16> {
     if bits.len() == 1 {
-        return bits.clone();
+        return bits.to_vec();
     }
 
     let mcnb = get_most_common_nth_bit(bits, k);
     bits.iter()
         .filter(|&n| (n & (1 << k)) >> k == mcnb)
-        .map(|&n| n)
+        .copied()
         .collect_vec()
 }
 
-fn filter_on_least_common_nth_bit(bits: &Vec<u16>, k: u16) -> Vec<u16> {
+fn filter_on_least_common_nth_bit(bits: &[u16], k: u16) -> Vec<u16> {
     if bits.len() == 1 {
-        return bits.clone();
+        return bits.to_vec();
     }
 
     let lcnb = get_least_common_nth_bit(bits, k);
     bits.iter()
         .filter(|&n| (n & (1 << k)) >> k == lcnb)
-        .map(|&n| n)
+        .copied()
         .collect_vec()
 }
 
 pub fn puzzle_1(input: &str) -> String {
     let (len, input) = parse_input(input);
     let gamma_rate = (0..len)
-        .map(|i| get_most_common_nth_bit(&input, i) * 1 << i)
+        .map(|i| get_most_common_nth_bit(&input, i) << i)
         .reduce(|acc, e| acc | e)
         .unwrap() as u32;
     let epsilon_rate = (0..len)
-        .map(|i| get_least_common_nth_bit(&input, i) * 1 << i)
+        .map(|i| get_least_common_nth_bit(&input, i) << i)
         .reduce(|acc, e| acc | e)
         .unwrap() as u32;
 
@@ -26,10 +26,7 @@ fn parse_grid(grid: &str) -> Grid {
 fn parse_input(input: &str) -> (Vec<u8>, Vec<Grid>) {
     let mut split = input.split("\n\n");
     let numbers = parse_numbers(split.next().unwrap());
-    let grid = split
-        .filter(|s| !s.is_empty())
-        .map(|lines| parse_grid(lines))
-        .collect();
+    let grid = split.filter(|s| !s.is_empty()).map(parse_grid).collect();
 
     (numbers, grid)
 }
@@ -37,22 +34,19 @@ fn parse_input(input: &str) -> (Vec<u8>, Vec<Grid>) {
 fn is_winning(grid: &Grid) -> bool {
     for l in 0..5 {
         let row_wins = grid[l].iter().all(|&n| n == 0);
-        let col_win = (0..5).all(|c| grid[c][l] == 0);
+        let col_win = grid.iter().all(|row| row[l] == 0);
         if row_wins || col_win {
             return true;
         }
     }
-    return false;
+    false
 }
 
 fn replace_number(grid: &mut Grid, number: u8) {
-    for l in 0..5 {
-        for c in 0..5 {
-            if grid[l][c] == number {
-                grid[l][c] = 0;
-            }
-        }
-    }
+    grid.iter_mut()
+        .flatten()
+        .filter(|n| **n == number)
+        .for_each(|n| *n = 0);
 }
 
 fn get_grid_score(grid: &Grid, number: u32) -> u32 {
@@ -71,7 +65,7 @@ pub fn puzzle_1(input: &str) -> String {
         for grid in grids.iter_mut() {
             replace_number(grid, n);
             if is_winning(grid) {
-                winning_grid = Some((n, grid.clone()));
+                winning_grid = Some((n, *grid));
                 break 'o;
             }
         }
@@ -92,7 +86,7 @@ pub fn puzzle_2(input: &str) -> String {
             }
             replace_number(grid, n);
             if is_winning(grid) {
-                winning_grid = Some((n, grid.clone()));
+                winning_grid = Some((n, *grid));
             }
         }
     }
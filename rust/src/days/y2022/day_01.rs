@@ -1,12 +1,10 @@
-fn load_input_data(input: &str) -> Vec<Vec<u32>> {
-    let elf_day_calories = input.split("\n\n");
-    elf_day_calories
-        .map(|subdata| -> Vec<u32> {
-            subdata
-                .lines()
-                .filter(|x| !x.is_empty())
-                .map(|x| x.parse::<u32>().unwrap())
-                .collect()
+use crate::parsing::{blocks, parse_lines_from, signed, ParseResult};
+
+fn load_input_data(input: &str) -> ParseResult<Vec<Vec<u32>>> {
+    blocks(input)
+        .into_iter()
+        .map(|(first_line, block)| {
+            parse_lines_from(first_line, block, signed).map_err(|e| e.expecting("calories"))
         })
         .collect()
 }
@@ -18,23 +16,23 @@ fn get_total_calories_by_elf(calories: Vec<Vec<u32>>) -> Vec<u32> {
         .collect()
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let calories = load_input_data(input);
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    let calories = load_input_data(input)?;
     let total_calories = get_total_calories_by_elf(calories);
 
     match total_calories.iter().max() {
-        Some(max) => max.to_string(),
-        None => 0.to_string(),
+        Some(max) => Ok(max.to_string()),
+        None => Ok(0.to_string()),
     }
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let calories = load_input_data(input);
+pub fn puzzle_2(input: &str) -> ParseResult<String> {
+    let calories = load_input_data(input)?;
     let mut total_calories = get_total_calories_by_elf(calories);
     assert!(total_calories.len() >= 3);
 
     total_calories.sort();
 
     let top_three = &total_calories[total_calories.len() - 3..];
-    top_three.iter().sum::<u32>().to_string()
+    Ok(top_three.iter().sum::<u32>().to_string())
 }
@@ -141,17 +141,11 @@ fn parse_hand_from_second_case(line: &str) -> Hand {
 }
 
 fn get_hands_from_input_first_case(input: &str) -> Vec<Hand> {
-    input
-        .lines()
-        .map(|line| parse_hand_from_first_case(line))
-        .collect()
+    input.lines().map(parse_hand_from_first_case).collect()
 }
 
 fn get_hands_from_input_second_case(input: &str) -> Vec<Hand> {
-    input
-        .lines()
-        .map(|line| parse_hand_from_second_case(line))
-        .collect()
+    input.lines().map(parse_hand_from_second_case).collect()
 }
 
 fn get_scores(hands: Vec<Hand>) -> Vec<u32> {
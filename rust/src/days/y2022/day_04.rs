@@ -1,44 +1,18 @@
-#[derive(PartialEq, Eq, Debug)]
-struct Job {
-    start: u32,
-    end: u32,
-}
-
-impl Job {
-    fn includes(&self, other: &Self) -> bool {
-        self.start <= other.start && self.end >= other.end
-    }
+use crate::structs::interval::Interval;
 
-    fn overlaps(&self, other: &Self) -> bool {
-        (self.start <= other.start && self.end >= other.start)
-            || (self.start <= other.end && self.end >= other.end)
-    }
-}
+type Job = Interval<u32>;
 
-impl TryFrom<&str> for Job {
-    type Error = ();
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let splitted = value.split_once('-');
-        match splitted {
-            Some(splitted) => {
-                let start = splitted.0.parse();
-                let end = splitted.1.parse();
-                if start.is_err() || end.is_err() {
-                    return Err(());
-                }
-                let start = start.unwrap();
-                let end = end.unwrap();
-                Ok(Self { start, end })
-            }
-            _ => Err(()),
-        }
-    }
+fn parse_job(value: &str) -> Result<Job, ()> {
+    let (start, end) = value.split_once('-').ok_or(())?;
+    let start = start.parse().map_err(|_| ())?;
+    let end = end.parse().map_err(|_| ())?;
+    Job::new(start, end).ok_or(())
 }
 
 fn parse_pair(input: &str) -> (Job, Job) {
     let pair = input.split_once(',').unwrap();
-    let first = Job::try_from(pair.0).unwrap();
-    let second = Job::try_from(pair.1).unwrap();
+    let first = parse_job(pair.0).unwrap();
+    let second = parse_job(pair.1).unwrap();
 
     (first, second)
 }
@@ -59,7 +33,7 @@ pub fn puzzle_2(input: &str) -> String {
         .split('\n')
         .filter(|x| !x.is_empty())
         .map(parse_pair)
-        .map(|(pa, pb)| pa.overlaps(&pb) || pb.overlaps(&pa))
+        .map(|(pa, pb)| pa.overlaps(&pb))
         .filter(|x| *x)
         .count()
         .to_string()
@@ -79,7 +53,7 @@ mod tests {
     #[test]
     fn test_parse_jobs() {
         let job = "2-4";
-        assert_eq!(Job::try_from(job).unwrap(), Job { start: 2, end: 4 });
+        assert_eq!(parse_job(job).unwrap(), Job::new(2, 4).unwrap());
     }
 
     #[test]
@@ -87,7 +61,7 @@ mod tests {
         let pair = "2-4,6-8";
         assert_eq!(
             parse_pair(pair),
-            (Job { start: 2, end: 4 }, Job { start: 6, end: 8 })
+            (Job::new(2, 4).unwrap(), Job::new(6, 8).unwrap())
         );
     }
 
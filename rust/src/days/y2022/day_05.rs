@@ -1,3 +1,7 @@
+use nom::{bytes::complete::tag, character::complete, sequence::tuple, IResult};
+
+use crate::parsing::{blocks, parse_lines_from, ParseError, ParseResult};
+
 type Piles = Vec<Vec<char>>;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -57,19 +61,21 @@ fn parse_piles(input: &str) -> Piles {
     piles
 }
 
-fn parse_steps(input: &str) -> Vec<Step> {
-    input
-        .split('\n')
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            let (number_str, rest_str) = line.split_once("from").unwrap();
-            let (from_str, to_str) = rest_str.split_once("to").unwrap();
-            let number = number_str.replace("move", "").trim().parse().unwrap();
-            let from = from_str.trim().parse().unwrap();
-            let to = to_str.trim().parse().unwrap();
-            Step { number, from, to }
-        })
-        .collect()
+fn parse_step(input: &str) -> IResult<&str, Step> {
+    let (input, (_, number, _, from, _, to)) = tuple((
+        tag("move "),
+        complete::u32,
+        tag(" from "),
+        complete::u32,
+        tag(" to "),
+        complete::u32,
+    ))(input)?;
+
+    Ok((input, Step { number, from, to }))
+}
+
+fn parse_steps(first_line: usize, input: &str) -> ParseResult<Vec<Step>> {
+    parse_lines_from(first_line, input, parse_step).map_err(|e| e.expecting("step"))
 }
 
 impl Step {
@@ -92,9 +98,16 @@ impl Step {
     }
 }
 
-fn parse_input(input: &str) -> (Piles, Vec<Step>) {
-    let (piles, steps) = input.split_once("\n\n").unwrap();
-    (parse_piles(piles), parse_steps(steps))
+fn parse_input(input: &str) -> ParseResult<(Piles, Vec<Step>)> {
+    match blocks(input)[..] {
+        [(_, piles), (first_line, steps)] => {
+            Ok((parse_piles(piles), parse_steps(first_line, steps)?))
+        }
+        _ => Err(ParseError::new(
+            1,
+            "expected the piles and the steps separated by a blank line",
+        )),
+    }
 }
 
 pub fn get_result(piles: &Piles) -> String {
@@ -108,8 +121,8 @@ pub fn get_result(piles: &Piles) -> String {
     result
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let parsed = parse_input(input);
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    let parsed = parse_input(input)?;
     let steps = parsed.1;
     let mut piles = parsed.0;
 
@@ -117,11 +130,11 @@ pub fn puzzle_1(input: &str) -> String {
         .into_iter()
         .for_each(|step| step.perform_move(&mut piles, Crane::Crane9000));
 
-    get_result(&piles)
+    Ok(get_result(&piles))
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let parsed = parse_input(input);
+pub fn puzzle_2(input: &str) -> ParseResult<String> {
+    let parsed = parse_input(input)?;
     let steps = parsed.1;
     let mut piles = parsed.0;
 
@@ -129,7 +142,7 @@ pub fn puzzle_2(input: &str) -> String {
         .into_iter()
         .for_each(|step| step.perform_move(&mut piles, Crane::Crane9001));
 
-    get_result(&piles)
+    Ok(get_result(&piles))
 }
 
 #[cfg(test)]
@@ -164,7 +177,7 @@ move 1 from 1 to 2";
     #[test]
     fn test_parse_step() {
         let step_str = "move 3 from 1 to 3";
-        let steps = parse_steps(step_str);
+        let steps = parse_steps(1, step_str).unwrap();
         assert_eq!(
             steps,
             vec![Step {
@@ -177,14 +190,14 @@ move 1 from 1 to 2";
 
     #[test]
     fn test_puzzle_1() {
-        let result = puzzle_1(INPUT);
+        let result = puzzle_1(INPUT).unwrap();
 
         assert_eq!(result, "CMZ");
     }
 
     #[test]
     fn test_puzzle_2() {
-        let result = puzzle_2(INPUT);
+        let result = puzzle_2(INPUT).unwrap();
 
         assert_eq!(result, "MCD");
     }
@@ -1,299 +1,299 @@
-use std::{
-    cell::RefCell,
-    rc::{Rc, Weak},
-};
+use std::{borrow::Cow, collections::BTreeMap};
 
-enum PathElement {
-    Current,
-    Parent,
-    Child(String),
-}
+use crate::{
+    parsing::{column_of, ParseError, ParseResult},
+    utils::is_verbose,
+};
 
-type Path = Vec<PathElement>;
+type NodeId = usize;
 
-impl TryFrom<&str> for PathElement {
-    type Error = ();
-    fn try_from(value: &str) -> Result<Self, ()> {
-        match value {
-            "." => Ok(Self::Current),
-            ".." => Ok(Self::Parent),
-            "" => Err(()),
-            string => Ok(Self::Child(string.to_string())),
-        }
-    }
-}
+const ROOT: NodeId = 0;
 
-fn make_path(value: &str) -> Result<Path, ()> {
-    let splitted = value.split('\n').map(PathElement::try_from);
-    if splitted.clone().any(|e| e.is_err()) {
-        return Err(());
-    }
-    Ok(splitted.map(|e| e.unwrap()).collect())
+#[derive(Debug)]
+enum NodeKind {
+    File,
+    Dir(BTreeMap<String, NodeId>),
 }
 
 #[derive(Debug)]
-struct File {
+struct Node {
     name: String,
-    size: u32,
-    parent: Weak<RefCell<Dir>>,
+    parent: Option<NodeId>,
+    kind: NodeKind,
+    // Size of the file, or total size of the directory content
+    size: u64,
 }
 
+// Nodes are stored in an arena, the root directory being the first one
 #[derive(Debug)]
-struct Dir {
-    name: String,
-    children: Vec<DirElement>,
-    parent: Option<Weak<RefCell<Dir>>>,
+struct FileSystem {
+    nodes: Vec<Node>,
 }
 
-impl Dir {
-    fn add_child_file(ptr: &Rc<RefCell<Self>>, size: u32, name: &str) {
-        let name = name.to_string();
-        let file = Rc::new(RefCell::new(File {
-            size,
-            name,
-            parent: Rc::downgrade(ptr),
-        }));
-        ptr.borrow_mut().children.push(DirElement::File(file));
+impl FileSystem {
+    fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: "/".to_string(),
+                parent: None,
+                kind: NodeKind::Dir(BTreeMap::new()),
+                size: 0,
+            }],
+        }
     }
 
-    fn add_child_dir(ptr: &Rc<RefCell<Self>>, name: &str) {
-        let name = name.to_string();
-        let dir = Rc::new(RefCell::new(Dir {
-            name,
-            parent: Some(Rc::downgrade(ptr)),
-            children: vec![],
-        }));
-        ptr.borrow_mut().children.push(DirElement::Dir(dir));
+    fn children(&self, dir: NodeId) -> Option<&BTreeMap<String, NodeId>> {
+        match &self.nodes[dir].kind {
+            NodeKind::Dir(children) => Some(children),
+            NodeKind::File => None,
+        }
     }
 
-    fn get_total_size(&self) -> u32 {
-        self.children.iter().map(|d| d.get_size()).sum()
+    fn is_dir(&self, node: NodeId) -> bool {
+        self.children(node).is_some()
     }
-}
 
-#[derive(Debug)]
-enum DirElement {
-    File(Rc<RefCell<File>>),
-    Dir(Rc<RefCell<Dir>>),
-}
+    fn size(&self, node: NodeId) -> u64 {
+        self.nodes[node].size
+    }
 
-impl Clone for DirElement {
-    fn clone(&self) -> Self {
-        match self {
-            DirElement::File(file) => DirElement::File(file.clone()),
-            DirElement::Dir(dir) => DirElement::Dir(dir.clone()),
-        }
+    fn add_node(&mut self, parent: NodeId, name: &str, kind: NodeKind) -> Option<NodeId> {
+        let id = self.nodes.len();
+        let NodeKind::Dir(children) = &mut self.nodes[parent].kind else {
+            return None;
+        };
+        children.insert(name.to_string(), id);
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent),
+            kind,
+            size: 0,
+        });
+        Some(id)
     }
-}
 
-impl DirElement {
-    fn get_name(&self) -> String {
-        match self {
-            DirElement::File(file) => file.borrow().name.clone(),
-            DirElement::Dir(dir) => dir.borrow().name.clone(),
+    // Returns the existing directory if it was already created
+    fn mkdir(&mut self, parent: NodeId, name: &str) -> Option<NodeId> {
+        match self.children(parent)?.get(name) {
+            Some(&existing) => self.is_dir(existing).then_some(existing),
+            None => self.add_node(parent, name, NodeKind::Dir(BTreeMap::new())),
         }
     }
 
-    fn get_size(&self) -> u32 {
-        match self {
-            DirElement::File(file) => file.borrow().size,
-            DirElement::Dir(dir) => dir.borrow().get_total_size(),
+    // Listing the same file again updates its size
+    fn add_file(&mut self, parent: NodeId, name: &str, size: u64) -> Option<NodeId> {
+        let file = match self.children(parent)?.get(name) {
+            Some(&existing) if self.is_dir(existing) => return None,
+            Some(&existing) => existing,
+            None => self.add_node(parent, name, NodeKind::File)?,
+        };
+
+        let previous_size = self.nodes[file].size;
+        let mut node = Some(file);
+        while let Some(id) = node {
+            self.nodes[id].size = self.nodes[id].size - previous_size + size;
+            node = self.nodes[id].parent;
         }
+        Some(file)
     }
 
-    fn get_child(&self, name: &str) -> Option<DirElement> {
-        match self {
-            DirElement::File(_) => None,
-            DirElement::Dir(dir) => {
-                for child in &dir.borrow().children {
-                    if child.get_name() == name {
-                        return Some(child.clone());
-                    }
-                }
-                None
-            }
-        }
+    // Absolute when starting with `/`, relative to `from` otherwise. The parent of the root
+    // is the root itself.
+    fn lookup(&self, from: NodeId, path: &str) -> Option<NodeId> {
+        let start = match path.starts_with('/') {
+            true => ROOT,
+            false => from,
+        };
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(start, |node, segment| match segment {
+                "." => Some(node),
+                ".." => Some(self.nodes[node].parent.unwrap_or(ROOT)),
+                name => self.children(node)?.get(name).copied(),
+            })
     }
 
-    fn get_parent(&self) -> Option<Rc<RefCell<Dir>>> {
-        match self {
-            DirElement::File(file) => file.borrow().parent.upgrade(),
-            DirElement::Dir(dir) => {
-                if let Some(parent) = &dir.borrow().parent {
-                    parent.upgrade()
-                } else {
-                    None
-                }
-            }
+    // Directory at the given path, the missing ones being created along the way
+    fn lookup_or_create(&mut self, from: NodeId, path: &str) -> Option<NodeId> {
+        if let Some(node) = self.lookup(from, path) {
+            return self.is_dir(node).then_some(node);
         }
+        let start = match path.starts_with('/') {
+            true => ROOT,
+            false => from,
+        };
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(start, |node, segment| match segment {
+                "." => Some(node),
+                ".." => Some(self.nodes[node].parent.unwrap_or(ROOT)),
+                name => self.mkdir(node, name),
+            })
     }
-}
 
-#[derive(Debug)]
-struct DirCursor {
-    cursor: DirElement,
-    root: Rc<RefCell<Dir>>,
-    is_ls: bool,
-}
-
-impl DirCursor {
-    fn create_empty() -> Self {
-        let directory = Rc::new(RefCell::new(Dir {
-            name: "".to_string(),
-            children: vec![],
-            parent: None,
-        }));
-        Self {
-            root: directory.clone(),
-            cursor: DirElement::Dir(directory),
-            is_ls: false,
+    fn path(&self, node: NodeId) -> String {
+        let mut names = vec![];
+        let mut current = node;
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(self.nodes[current].name.as_str());
+            current = parent;
         }
+        names.reverse();
+        format!("/{}", names.join("/"))
     }
 
-    fn cd(&mut self, path_str: &str) -> Result<(), ()> {
-        if path_str == "/" {
-            self.cursor = DirElement::Dir(self.root.clone());
-            return Ok(());
-        }
-        let path = make_path(path_str)?;
-        for path_element in path {
-            match path_element {
-                PathElement::Current => continue,
-                PathElement::Parent => {
-                    if let Some(parent) = self.cursor.get_parent() {
-                        self.cursor = DirElement::Dir(parent);
-                    } else {
-                        return Err(());
-                    }
-                }
-                PathElement::Child(name) => {
-                    if let Some(child) = self.cursor.get_child(&name) {
-                        self.cursor = child;
-                    } else {
-                        return Err(());
-                    };
-                }
-            }
-        }
-        Ok(())
+    fn directories(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|&id| self.is_dir(id))
     }
 
-    fn handle_command_line(&mut self, line: &str) -> Result<(), ()> {
-        self.is_ls = false;
-        match line.split_whitespace().next() {
-            None => Err(()),
-            Some("ls") => {
-                self.is_ls = true;
-                Ok(())
-            }
-            Some("cd") => self.cd(&line[3..]),
-            Some(_) => Err(()),
-        }
+    // Total size of every directory, like `du`
+    fn du(&self) -> String {
+        let mut lines: Vec<_> = self
+            .directories()
+            .map(|dir| format!("{}\t{}", self.size(dir), self.path(dir)))
+            .collect();
+        lines.sort_by(|a, b| a.split('\t').nth(1).cmp(&b.split('\t').nth(1)));
+        lines.join("\n")
     }
 
-    fn handle_ls_line(&mut self, line: &str) -> Result<(), ()> {
-        if let Some((size_or_dir, name)) = line.split_once(' ') {
-            match &self.cursor {
-                DirElement::File(_) => {
-                    return Err(());
-                }
-                DirElement::Dir(cursor) => {
-                    let mut is_file = false;
-                    let size_or_dir = size_or_dir.parse::<u32>().map(|s| {
-                        is_file = true;
-                        s
-                    });
-                    if is_file {
-                        let size = size_or_dir.unwrap();
-                        Dir::add_child_file(cursor, size, name);
-                    } else {
-                        Dir::add_child_dir(cursor, name);
+    // Same format as the example of the puzzle
+    fn tree(&self) -> String {
+        fn write_node(fs: &FileSystem, node: NodeId, depth: usize, lines: &mut Vec<String>) {
+            let indent = "  ".repeat(depth);
+            let name = &fs.nodes[node].name;
+            match fs.children(node) {
+                Some(children) => {
+                    lines.push(format!("{}- {} (dir)", indent, name));
+                    for &child in children.values() {
+                        write_node(fs, child, depth + 1, lines);
                     }
-                    return Ok(());
                 }
+                None => lines.push(format!(
+                    "{}- {} (file, size={})",
+                    indent,
+                    name,
+                    fs.size(node)
+                )),
             }
         }
-        Err(())
-    }
-
-    fn handle_line(&mut self, line: &str) -> Result<(), ()> {
-        if line.chars().next().is_some_and(|c| c == '$') {
-            self.handle_command_line(&line[2..])
-        } else {
-            if !self.is_ls {
-                return Err(());
-            }
-            self.handle_ls_line(line)
-        }
-    }
-}
 
-fn parse_output(text: &str) -> DirCursor {
-    let mut dir = DirCursor::create_empty();
-    for line in text.split('\n').map(|l| l.trim()).filter(|l| !l.is_empty()) {
-        dir.handle_line(line).unwrap();
+        let mut lines = vec![];
+        write_node(self, ROOT, 0, &mut lines);
+        lines.join("\n")
     }
-
-    dir
 }
 
-fn get_small_dir_recur(dir: &Rc<RefCell<Dir>>, result: &mut Vec<Rc<RefCell<Dir>>>, limit: u32) {
-    if dir.borrow().get_total_size() <= limit {
-        result.push(dir.clone());
-    }
-    for child in &dir.borrow().children {
-        match child {
-            DirElement::File(_) => {}
-            DirElement::Dir(dir) => get_small_dir_recur(dir, result, limit),
-        }
+// The home directory is the root of the file system
+fn expand_home(path: &str) -> Cow<'_, str> {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("/{}", rest).into(),
+        _ => path.into(),
     }
 }
 
-fn get_all_dir_recur(dir: &Rc<RefCell<Dir>>, result: &mut Vec<Rc<RefCell<Dir>>>) {
-    result.push(dir.clone());
-    for child in &dir.borrow().children {
-        match child {
-            DirElement::File(_) => {}
-            DirElement::Dir(dir) => get_all_dir_recur(dir, result),
+// Replays a shell transcript, directories are created when entered or listed before being seen
+fn parse_output(text: &str) -> ParseResult<FileSystem> {
+    let mut fs = FileSystem::new();
+    let mut cwd = ROOT;
+    // Directory whose content is being listed
+    let mut listing = None;
+
+    for (i, line) in text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+    {
+        let error = |word: &str, message: String| {
+            ParseError::at(i + 1, line, column_of(line, word), message)
+        };
+        let words: Vec<_> = line.split_whitespace().collect();
+        match (&words[..], listing) {
+            (["$", "cd"], _) => {
+                listing = None;
+                cwd = ROOT;
+            }
+            (["$", "cd", path], _) => {
+                listing = None;
+                cwd = fs
+                    .lookup_or_create(cwd, &expand_home(path))
+                    .ok_or_else(|| error(path, format!("`{}` is not a directory", path)))?;
+            }
+            (["$", "ls"], _) => listing = Some(cwd),
+            (["$", "ls", path], _) => {
+                let dir = fs
+                    .lookup_or_create(cwd, &expand_home(path))
+                    .ok_or_else(|| error(path, format!("`{}` is not a directory", path)))?;
+                listing = Some(dir);
+            }
+            (["$", command @ ("cd" | "ls"), _, extra, ..], _) => {
+                return Err(error(
+                    extra,
+                    format!("too many arguments for `{}`", command),
+                ))
+            }
+            (["$", command, ..], _) => {
+                return Err(error(command, format!("unknown command `{}`", command)))
+            }
+            ([prompt @ "$"], _) => return Err(error(prompt, "missing command".to_string())),
+            (["dir", name], Some(dir)) => {
+                fs.mkdir(dir, name)
+                    .ok_or_else(|| error(name, format!("a file is named `{}`", name)))?;
+            }
+            ([size, name], Some(dir)) => {
+                let size = size
+                    .parse()
+                    .map_err(|_| error(size, format!("invalid file size `{}`", size)))?;
+                fs.add_file(dir, name, size)
+                    .ok_or_else(|| error(name, format!("a directory is named `{}`", name)))?;
+            }
+            ([first, ..], Some(_)) => {
+                return Err(error(first, "unexpected `ls` output".to_string()))
+            }
+            ([first, ..], None) => {
+                return Err(error(first, "output outside of a command".to_string()))
+            }
+            ([], _) => unreachable!(),
         }
     }
-}
-
-pub fn puzzle_1(input: &str) -> String {
-    let dir = parse_output(input);
-    let mut small_dirs = vec![];
-    get_small_dir_recur(&dir.root, &mut small_dirs, 100_000);
 
-    let size: u32 = small_dirs
-        .into_iter()
-        .map(|d| d.borrow().get_total_size())
-        .sum();
-
-    size.to_string()
+    Ok(fs)
 }
 
-pub fn puzzle_2(input: &str) -> String {
+// Sum of the sizes of the small directories, and size of the smallest directory to delete
+fn solve(fs: &FileSystem) -> (u64, Option<u64>) {
     let target = 30_000_000;
     let fs_total = 70_000_000;
+    let to_save = (fs.size(ROOT) + target).saturating_sub(fs_total);
+
+    fs.directories()
+        .map(|dir| fs.size(dir))
+        .fold((0, None), |(small_dirs, to_delete), size| {
+            let small_dirs = match size <= 100_000 {
+                true => small_dirs + size,
+                false => small_dirs,
+            };
+            let to_delete = match size >= to_save {
+                true => Some(to_delete.map_or(size, |best: u64| best.min(size))),
+                false => to_delete,
+            };
+            (small_dirs, to_delete)
+        })
+}
 
-    let dir = parse_output(input);
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    let fs = parse_output(input)?;
+    if is_verbose() {
+        println!("{}\n\n{}", fs.tree(), fs.du());
+    }
+    Ok(solve(&fs).0.to_string())
+}
 
-    let space_left = fs_total - dir.root.borrow().get_total_size();
-    if space_left > target {
-        return String::new();
+pub fn puzzle_2(input: &str) -> ParseResult<String> {
+    let fs = parse_output(input)?;
+    match solve(&fs).1 {
+        Some(size) => Ok(size.to_string()),
+        None => Ok(String::new()),
     }
-    let to_save = target - space_left;
-
-    let mut all_dirs = vec![];
-    get_all_dir_recur(&dir.root, &mut all_dirs);
-
-    let mut sizes: Vec<_> = all_dirs
-        .into_iter()
-        .map(|d| d.borrow().get_total_size())
-        .filter(|s| *s >= to_save)
-        .collect();
-    sizes.sort();
-    sizes.first().unwrap().to_string()
 }
 
 #[cfg(test)]
@@ -326,27 +326,95 @@ $ ls
 
     #[test]
     fn test_create_empty_dir() {
-        let output = "$ cd /";
-        let dir = parse_output(output);
-        assert_eq!(dir.root.borrow().children.len(), 0);
+        let fs = parse_output("$ cd /").unwrap();
+        assert_eq!(fs.children(ROOT).unwrap().len(), 0);
     }
 
     #[test]
     fn test_create_dirs() {
         let output = "$ cd /\n$ ls\ndir a\n12345 b\n";
-        let dir = parse_output(output);
-        assert_eq!(dir.root.borrow().children.len(), 2);
-        assert_eq!(dir.root.borrow().children.get(0).unwrap().get_size(), 0);
-        assert_eq!(dir.root.borrow().children.get(1).unwrap().get_size(), 12345);
+        let fs = parse_output(output).unwrap();
+        assert_eq!(fs.children(ROOT).unwrap().len(), 2);
+        assert_eq!(fs.size(fs.lookup(ROOT, "a").unwrap()), 0);
+        assert_eq!(fs.size(fs.lookup(ROOT, "/b").unwrap()), 12345);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let fs = parse_output(INPUT).unwrap();
+        let a = fs.lookup(ROOT, "/a").unwrap();
+        let e = fs.lookup(a, "e").unwrap();
+        assert_eq!(fs.lookup(e, "../../d/k"), fs.lookup(ROOT, "/d/./k"));
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.size(a), 94853);
+        assert_eq!(fs.lookup(a, "missing"), None);
+    }
+
+    #[test]
+    fn test_tolerant_transcript() {
+        // `cd` before `ls`, and the same listing twice
+        let output = "$ cd /\n$ cd a\n$ ls\n10 f\n$ cd /\n$ ls\ndir a\n5 g\n$ ls\ndir a\n5 g";
+        let fs = parse_output(output).unwrap();
+        assert_eq!(fs.size(ROOT), 15);
+        assert_eq!(fs.children(ROOT).unwrap().len(), 2);
+
+        assert_eq!(parse_output("$ cd /\ndir a").unwrap_err().line, 2);
+        assert_eq!(parse_output("$ ls\n1 a\n$ cd a").unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn test_shell_paths() {
+        let output = "$ cd /a/b\n$ ls\n10 f\n$ cd ../c\n$ ls ~/a/d\n20 g\n$ cd ~\n$ ls a/b\n30 f";
+        let fs = parse_output(output).unwrap();
+        assert_eq!(fs.size(fs.lookup(ROOT, "/a/b").unwrap()), 30);
+        assert_eq!(fs.size(fs.lookup(ROOT, "/a/d").unwrap()), 20);
+        assert!(fs.lookup(ROOT, "/a/c").is_some());
+        assert_eq!(fs.size(ROOT), 50);
+
+        let fs = parse_output("$ cd a\n$ cd\n$ ls\n5 h").unwrap();
+        assert!(fs.lookup(ROOT, "/h").is_some());
+
+        // Like in a shell, `..` at the root stays there
+        let fs = parse_output("$ cd /\n$ cd ..\n$ ls ../..\n5 i").unwrap();
+        assert_eq!(fs.lookup(ROOT, ".."), Some(ROOT));
+        assert!(fs.lookup(ROOT, "/i").is_some());
+    }
+
+    #[test]
+    fn test_transcript_errors() {
+        let error = parse_output("$ cd /\n$ ls\n1 a\n$ rm a").unwrap_err();
+        assert_eq!((error.line, error.column), (4, Some(3)));
+        assert_eq!(error.message, "unknown command `rm`");
+
+        let error = parse_output("$ cd /\ntotal 0").unwrap_err();
+        assert_eq!((error.line, error.column), (2, Some(1)));
+        assert_eq!(error.message, "output outside of a command");
+
+        let error = parse_output("$ ls\n1 a\n$ ls a/b").unwrap_err();
+        assert_eq!((error.line, error.column), (3, Some(6)));
+
+        let error = parse_output("$ ls\n12k a").unwrap_err();
+        assert_eq!(error.message, "invalid file size `12k`");
+    }
+
+    #[test]
+    fn test_reports() {
+        let fs = parse_output(INPUT).unwrap();
+        assert_eq!(fs.du(), "48381165\t/\n94853\t/a\n584\t/a/e\n24933642\t/d");
+        let tree = fs.tree();
+        assert!(
+            tree.starts_with("- / (dir)\n  - a (dir)\n    - e (dir)\n      - i (file, size=584)")
+        );
+        assert!(tree.ends_with("    - k (file, size=7214296)"));
     }
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(puzzle_1(INPUT), "95437");
+        assert_eq!(puzzle_1(INPUT).unwrap(), "95437");
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(puzzle_2(INPUT), "24933642");
+        assert_eq!(puzzle_2(INPUT).unwrap(), "24933642");
     }
 }
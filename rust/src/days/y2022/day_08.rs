@@ -127,8 +127,7 @@ fn get_visibility_length(forest: &TreeGrid, side: Side, pos: Point) -> usize {
 fn is_tree_visible(forest: &TreeGrid, pos: Point) -> bool {
     [Side::North, Side::West, Side::South, Side::East]
         .iter()
-        .map(|side| is_visible_from_side(forest, *side, pos))
-        .any(|x| x)
+        .any(|side| is_visible_from_side(forest, *side, pos))
 }
 
 fn get_visible_trees(input: &TreeGrid) -> Vec<Vec<bool>> {
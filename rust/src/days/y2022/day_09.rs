@@ -1,6 +1,17 @@
 use std::collections::BTreeSet;
 
-use crate::structs::geometry::Point2;
+use nom::{
+    character::complete::{self, anychar, space1},
+    combinator::map_res,
+    sequence::separated_pair,
+    IResult,
+};
+
+use crate::{
+    parsing::{parse_lines, ParseResult},
+    render::{self, Color, Image},
+    structs::geometry::{Direction4, Point2},
+};
 
 type Point = Point2<i32>;
 type Vector = Point2<i32>;
@@ -17,7 +28,7 @@ impl Rope {
     }
 
     fn get_tail(&self) -> Option<&Point> {
-        return self.knots.last();
+        self.knots.last()
     }
 
     fn move_head(&mut self, vector: Vector) -> Vec<Point> {
@@ -53,70 +64,18 @@ impl Rope {
     }
 }
 
-#[allow(dead_code)]
-fn print_debug_pos(input: &BTreeSet<Point>) {
-    let mut max_x = 0;
-    let mut max_y = 0;
-    let mut min_x = 0;
-    let mut min_y = 0;
+fn parse_movement(input: &str) -> IResult<&str, Vector> {
+    let (input, (direction, distance)) = separated_pair(
+        map_res(anychar, Direction4::try_from),
+        space1,
+        complete::i32,
+    )(input)?;
 
-    input.iter().for_each(|Point { x, y }| {
-        if x < &min_x {
-            min_x = *x;
-        }
-        if y < &min_y {
-            min_y = *y;
-        }
-        if x > &max_x {
-            max_x = *x;
-        }
-        if y > &max_y {
-            max_y = *y;
-        }
-    });
-
-    for y in (min_y..=max_y).rev() {
-        let mut line = String::new();
-        line.push_str(&format!("{}\t", y));
-        for x in min_x..=max_x {
-            if x == 0 && y == 0 {
-                line.push('s');
-            } else if input.contains(&Point { x, y }) {
-                line.push('#');
-            } else {
-                line.push('.');
-            }
-        }
-        println!("{line}");
-    }
+    Ok((input, direction.to_vector() * distance))
 }
 
-fn parse_direction(value: char) -> Result<Point, ()> {
-    match value {
-        'U' => Ok(Point::unit_y()),
-        'D' => Ok(-Point::unit_y()),
-        'R' => Ok(Point::unit_x()),
-        'L' => Ok(-Point::unit_x()),
-        _ => Err(()),
-    }
-}
-
-fn parse_input(input: &str) -> Vec<Vector> {
-    input
-        .split('\n')
-        .filter(|l| !l.is_empty())
-        .map(|l| {
-            let (direction, distance) = l.split_once(' ').unwrap();
-            let distance = distance.parse().ok().unwrap();
-            let vector = direction
-                .chars()
-                .next()
-                .and_then(|c| parse_direction(c).ok())
-                .unwrap()
-                * distance;
-            vector
-        })
-        .collect()
+fn parse_input(input: &str) -> ParseResult<Vec<Vector>> {
+    parse_lines(input, parse_movement).map_err(|e| e.expecting("movement"))
 }
 
 fn get_tail_pos(mouvements: &Vec<Vector>, rope: &mut Rope) -> BTreeSet<Point> {
@@ -127,28 +86,33 @@ fn get_tail_pos(mouvements: &Vec<Vector>, rope: &mut Rope) -> BTreeSet<Point> {
         for pos_tail in rope.move_head(*vector) {
             positions.insert(pos_tail);
         }
-        // print_debug_pos(&positions);
     }
 
     positions
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let mouvements = parse_input(input);
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    let mouvements = parse_input(input)?;
 
     let mut rope = Rope::new(2, Point { x: 0, y: 0 });
 
     let positions = get_tail_pos(&mouvements, &mut rope);
-    positions.len().to_string()
+    render::export("2022_09_tail_positions_1.ppm", || {
+        Image::from_points(&positions, Color::WHITE, Color::BLACK).flip_vertical()
+    });
+    Ok(positions.len().to_string())
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let mouvements = parse_input(input);
+pub fn puzzle_2(input: &str) -> ParseResult<String> {
+    let mouvements = parse_input(input)?;
 
     let mut rope = Rope::new(10, Point { x: 0, y: 0 });
 
     let positions = get_tail_pos(&mouvements, &mut rope);
-    positions.len().to_string()
+    render::export("2022_09_tail_positions_2.ppm", || {
+        Image::from_points(&positions, Color::WHITE, Color::BLACK).flip_vertical()
+    });
+    Ok(positions.len().to_string())
 }
 
 #[cfg(test)]
@@ -179,17 +143,18 @@ U 20";
         let input = "R 4\nU 4\n";
         assert_eq!(
             parse_input(input),
-            vec![Vector::unit_x() * 4, Vector::unit_y() * 4]
+            Ok(vec![Vector::unit_x() * 4, Vector::unit_y() * 4])
         );
+        assert_eq!(parse_input("R 4\nX 2").unwrap_err().line, 2);
     }
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(puzzle_1(INPUT), "88");
+        assert_eq!(puzzle_1(INPUT).unwrap(), "88");
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(puzzle_2(INPUT), "36");
+        assert_eq!(puzzle_2(INPUT).unwrap(), "36");
     }
 }
@@ -1,129 +1,326 @@
-use std::collections::BTreeMap;
-
-use itertools::Itertools;
-use nom::character::complete::{alpha1, newline};
-use nom::character::streaming::space1;
+use nom::character::complete::{alpha1, space1};
 use nom::error::{Error, ErrorKind};
-use nom::multi::separated_list0;
-use nom::sequence::tuple;
+use nom::sequence::preceded;
 use nom::IResult;
 
+use crate::{
+    ocr,
+    parsing::{parse_lines, ParseResult},
+    render::{self, black_on_white, Image},
+    utils::is_verbose,
+};
+
+type Register = usize;
+
+const X: Register = 0;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Instruction {
-    Noop,
-    Addx(i32),
+enum Operation {
+    Nothing,
+    Add(Register),
 }
 
-struct Cpu {
-    counter: usize,
-    register_x: i32,
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InstructionSpec {
+    name: &'static str,
+    cycles: usize,
+    operation: Operation,
 }
 
-impl Instruction {
-    fn parser(input: &str) -> IResult<&str, Instruction> {
-        let (input, instruction) = alpha1(input)?;
-        match instruction {
-            "noop" => Ok((input, Instruction::Noop)),
-            "addx" => {
-                let (input, (_, x)) = tuple((space1, nom::character::complete::i32))(input)?;
-                Ok((input, Instruction::Addx(x)))
+// Registers with their initial value, and the instructions the CPU understands
+#[derive(Debug, Clone)]
+struct InstructionSet {
+    registers: Vec<(&'static str, i32)>,
+    instructions: Vec<InstructionSpec>,
+}
+
+// `opcode` is the index of the instruction in its set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Instruction {
+    opcode: usize,
+    argument: i32,
+}
+
+impl InstructionSet {
+    // The handheld device of the puzzle
+    fn device() -> Self {
+        Self {
+            registers: vec![("X", 1)],
+            instructions: vec![
+                InstructionSpec {
+                    name: "noop",
+                    cycles: 1,
+                    operation: Operation::Nothing,
+                },
+                InstructionSpec {
+                    name: "addx",
+                    cycles: 2,
+                    operation: Operation::Add(X),
+                },
+            ],
+        }
+    }
+
+    fn instruction(&self, name: &str, argument: i32) -> Option<Instruction> {
+        let opcode = self.instructions.iter().position(|i| i.name == name)?;
+        Some(Instruction { opcode, argument })
+    }
+
+    fn spec(&self, instruction: Instruction) -> &InstructionSpec {
+        &self.instructions[instruction.opcode]
+    }
+
+    fn format(&self, instruction: Instruction) -> String {
+        let spec = self.spec(instruction);
+        match spec.operation {
+            Operation::Nothing => spec.name.to_string(),
+            _ => format!("{} {}", spec.name, instruction.argument),
+        }
+    }
+
+    fn format_registers(&self, registers: &[i32]) -> String {
+        self.registers
+            .iter()
+            .zip(registers)
+            .map(|((name, _), value)| format!("{} = {}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn parser<'a>(&'a self) -> impl FnMut(&'a str) -> IResult<&'a str, Instruction> + 'a {
+        move |input| {
+            let (rest, name) = alpha1(input)?;
+            let Some(instruction) = self.instruction(name, 0) else {
+                return Err(nom::Err::Failure(Error::new(input, ErrorKind::Switch)));
+            };
+            match self.spec(instruction).operation {
+                Operation::Nothing => Ok((rest, instruction)),
+                Operation::Add(_) => {
+                    let (rest, argument) = preceded(space1, nom::character::complete::i32)(rest)?;
+                    Ok((
+                        rest,
+                        Instruction {
+                            argument,
+                            ..instruction
+                        },
+                    ))
+                }
             }
-            other => IResult::Err(nom::Err::Failure(Error::new(other, ErrorKind::Switch))),
         }
     }
 }
 
-impl Cpu {
-    fn new() -> Self {
+// Called around the cycles of the CPU, the cycles being numbered from 1
+trait CycleHooks {
+    // Before the first cycle of an instruction
+    fn start(&mut self, _cycle: usize, _instruction: Instruction, _registers: &[i32]) {}
+
+    // While the cycle is running, the registers still hold the values from before the instruction
+    fn during(&mut self, _cycle: usize, _registers: &[i32]) {}
+
+    // After the last cycle of an instruction, once its effect is applied
+    fn end(&mut self, _cycle: usize, _instruction: Instruction, _registers: &[i32]) {}
+}
+
+impl<H> CycleHooks for &mut H
+where
+    H: CycleHooks,
+{
+    fn start(&mut self, cycle: usize, instruction: Instruction, registers: &[i32]) {
+        (**self).start(cycle, instruction, registers);
+    }
+
+    fn during(&mut self, cycle: usize, registers: &[i32]) {
+        (**self).during(cycle, registers);
+    }
+
+    fn end(&mut self, cycle: usize, instruction: Instruction, registers: &[i32]) {
+        (**self).end(cycle, instruction, registers);
+    }
+}
+
+impl<H> CycleHooks for Option<H>
+where
+    H: CycleHooks,
+{
+    fn start(&mut self, cycle: usize, instruction: Instruction, registers: &[i32]) {
+        if let Some(hooks) = self {
+            hooks.start(cycle, instruction, registers);
+        }
+    }
+
+    fn during(&mut self, cycle: usize, registers: &[i32]) {
+        if let Some(hooks) = self {
+            hooks.during(cycle, registers);
+        }
+    }
+
+    fn end(&mut self, cycle: usize, instruction: Instruction, registers: &[i32]) {
+        if let Some(hooks) = self {
+            hooks.end(cycle, instruction, registers);
+        }
+    }
+}
+
+impl<A, B> CycleHooks for (A, B)
+where
+    A: CycleHooks,
+    B: CycleHooks,
+{
+    fn start(&mut self, cycle: usize, instruction: Instruction, registers: &[i32]) {
+        self.0.start(cycle, instruction, registers);
+        self.1.start(cycle, instruction, registers);
+    }
+
+    fn during(&mut self, cycle: usize, registers: &[i32]) {
+        self.0.during(cycle, registers);
+        self.1.during(cycle, registers);
+    }
+
+    fn end(&mut self, cycle: usize, instruction: Instruction, registers: &[i32]) {
+        self.0.end(cycle, instruction, registers);
+        self.1.end(cycle, instruction, registers);
+    }
+}
+
+struct Cpu<'a> {
+    instruction_set: &'a InstructionSet,
+    cycle: usize,
+    registers: Vec<i32>,
+}
+
+impl<'a> Cpu<'a> {
+    fn new(instruction_set: &'a InstructionSet) -> Self {
         Cpu {
-            counter: 0,
-            register_x: 1,
+            instruction_set,
+            cycle: 0,
+            registers: instruction_set.registers.iter().map(|r| r.1).collect(),
         }
     }
 
-    fn execute(&mut self, instruction: Instruction) {
-        match instruction {
-            Instruction::Noop => self.cycle(),
-            Instruction::Addx(x) => {
-                self.cycle();
-                self.cycle();
-                self.register_x += x;
-            }
+    fn execute(&mut self, instruction: Instruction, hooks: &mut impl CycleHooks) {
+        let spec = self.instruction_set.spec(instruction);
+        hooks.start(self.cycle + 1, instruction, &self.registers);
+        for _ in 0..spec.cycles {
+            self.cycle += 1;
+            hooks.during(self.cycle, &self.registers);
         }
+        match spec.operation {
+            Operation::Nothing => (),
+            Operation::Add(register) => self.registers[register] += instruction.argument,
+        }
+        hooks.end(self.cycle, instruction, &self.registers);
     }
 
-    fn cycle(&mut self) {
-        self.counter += 1;
+    fn run(&mut self, program: &[Instruction], hooks: &mut impl CycleHooks) {
+        for &instruction in program {
+            self.execute(instruction, hooks);
+        }
     }
 }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
-    let (_, output) = separated_list0(newline, Instruction::parser)(input).unwrap();
-    output
+// Sum of the signal strengths during the cycles `start`, `start + period`, ...
+struct SignalStrength {
+    start: usize,
+    period: usize,
+    total: i32,
 }
 
-fn complete_history(history: BTreeMap<usize, i32>) -> Vec<i32> {
-    let mut output = vec![];
-    let max_keys = history.keys().max();
-    let min_keys = history.keys().min();
-    if max_keys.is_none() || min_keys.is_none() {
-        return output;
+impl SignalStrength {
+    fn new(start: usize, period: usize) -> Self {
+        Self {
+            start,
+            period,
+            total: 0,
+        }
     }
-    let max_keys = max_keys.unwrap();
+}
 
-    for i in 0..=*max_keys {
-        if &i < min_keys.unwrap() {
-            output.push(0);
-            continue;
+impl CycleHooks for SignalStrength {
+    fn during(&mut self, cycle: usize, registers: &[i32]) {
+        if cycle >= self.start && (cycle - self.start).is_multiple_of(self.period) {
+            self.total += cycle as i32 * registers[X];
         }
-        let value = history
-            .iter()
-            .filter(|(&k, _)| k <= i)
-            .max_by_key(|(&k, _)| k);
-        if let Some(value) = value {
-            output.push(*value.1);
+    }
+}
+
+// Draws one pixel per cycle, lit when the 3 pixels wide sprite is on it
+struct Crt {
+    width: usize,
+    pixels: Vec<Vec<bool>>,
+}
+
+impl Crt {
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            pixels: vec![],
         }
     }
-    output
 }
 
-fn get_signal_strengths(start: usize, increment: usize, history: BTreeMap<usize, i32>) -> Vec<i32> {
-    let increment_selector = |key: usize| {
-        if key < start.saturating_sub(1) {
-            return false;
+impl CycleHooks for Crt {
+    fn during(&mut self, cycle: usize, registers: &[i32]) {
+        let column = (cycle - 1) % self.width;
+        if column == 0 {
+            self.pixels.push(vec![]);
         }
-        (key + 1 - start) % increment == 0
-    };
-
-    let history = complete_history(history);
-    history[..history.len() - 1]
-        .iter()
-        .enumerate()
-        .filter(|&(key, _)| increment_selector(key))
-        .map(|(key, value)| ((key + 1) as i32, value))
-        .map(|(key, value)| key * value)
-        .collect()
+        let lit = (registers[X] - column as i32).abs() < 2;
+        self.pixels.last_mut().unwrap().push(lit);
+    }
 }
 
-fn get_drawing(length: usize, history: BTreeMap<usize, i32>) -> Vec<Vec<bool>> {
-    let mut output = vec![];
-    let history = complete_history(history);
-    history
-        .iter()
-        .enumerate()
-        .chunks(length)
-        .into_iter()
-        .for_each(|range| {
-            let row = range
-                .map(|(i, v)| (*v - (i % length) as i32).abs() < 2)
-                .collect();
-            output.push(row);
-        });
+// Cycles are aligned like in the example of the puzzle, which shows the CRT instead of
+// the registers
+struct Tracer<'a> {
+    instruction_set: &'a InstructionSet,
+    lines: Vec<String>,
+}
 
-    output
+impl<'a> Tracer<'a> {
+    fn new(instruction_set: &'a InstructionSet) -> Self {
+        Self {
+            instruction_set,
+            lines: vec![],
+        }
+    }
 }
 
-fn format_drawing(drawing: Vec<Vec<bool>>) -> String {
+impl CycleHooks for Tracer<'_> {
+    fn start(&mut self, cycle: usize, instruction: Instruction, _registers: &[i32]) {
+        self.lines.push(format!(
+            "Start cycle{:>4}: begin executing {}",
+            cycle,
+            self.instruction_set.format(instruction)
+        ));
+    }
+
+    fn during(&mut self, cycle: usize, registers: &[i32]) {
+        self.lines.push(format!(
+            "During cycle{:>3}: {}",
+            cycle,
+            self.instruction_set.format_registers(registers)
+        ));
+    }
+
+    fn end(&mut self, cycle: usize, instruction: Instruction, registers: &[i32]) {
+        self.lines.push(format!(
+            "End of cycle{:>3}: finish executing {} ({})",
+            cycle,
+            self.instruction_set.format(instruction),
+            self.instruction_set.format_registers(registers)
+        ));
+    }
+}
+
+fn parse_input<'a>(
+    instruction_set: &'a InstructionSet,
+    input: &'a str,
+) -> ParseResult<Vec<Instruction>> {
+    parse_lines(input, instruction_set.parser()).map_err(|e| e.expecting("instruction"))
+}
+
+fn format_drawing(drawing: &[Vec<bool>]) -> String {
     let mut output = String::new();
     drawing.iter().for_each(|line| {
         let mut out = String::new();
@@ -138,37 +335,43 @@ fn format_drawing(drawing: Vec<Vec<bool>>) -> String {
     output
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let instructions = parse_input(input);
-
-    let mut history = BTreeMap::new();
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    let instruction_set = InstructionSet::device();
+    let instructions = parse_input(&instruction_set, input)?;
 
-    let mut cpu = Cpu::new();
-    cpu.register_x = 1;
-    history.insert(cpu.counter, cpu.register_x);
-    instructions.iter().for_each(|&instruction| {
-        cpu.execute(instruction);
-        history.insert(cpu.counter, cpu.register_x);
-    });
-
-    let strength: i32 = get_signal_strengths(20, 40, history).iter().sum();
+    let mut cpu = Cpu::new(&instruction_set);
+    let tracer = is_verbose().then(|| Tracer::new(&instruction_set));
+    let mut hooks = (SignalStrength::new(20, 40), tracer);
+    cpu.run(&instructions, &mut hooks);
+    if let Some(tracer) = hooks.1 {
+        println!("{}", tracer.lines.join("\n"));
+    }
 
-    strength.to_string()
+    Ok(hooks.0.total.to_string())
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let instructions = parse_input(input);
+pub fn puzzle_2(input: &str) -> ParseResult<String> {
+    let instruction_set = InstructionSet::device();
+    let instructions = parse_input(&instruction_set, input)?;
 
-    let mut history = BTreeMap::new();
+    let mut cpu = Cpu::new(&instruction_set);
+    let mut crt = Crt::new(40);
+    cpu.run(&instructions, &mut crt);
 
-    let mut cpu = Cpu::new();
-    history.insert(cpu.counter, cpu.register_x);
-    instructions.iter().for_each(|&instruction| {
-        cpu.execute(instruction);
-        history.insert(cpu.counter, cpu.register_x);
+    render::export("2022_10_crt.ppm", || {
+        Image::from_grid(&crt.pixels, black_on_white).scale(8)
     });
-    let drawing = get_drawing(40, history);
-    format_drawing(drawing)
+    if is_verbose() {
+        println!("{}", format_drawing(&crt.pixels));
+    }
+    // The picture is still returned when it cannot be read
+    match ocr::recognize(&crt.pixels) {
+        Ok(text) => Ok(text),
+        Err(error) => {
+            eprintln!("{}", error);
+            Ok(format_drawing(&crt.pixels))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -324,42 +527,93 @@ noop";
 
     #[test]
     fn test_parse_instruction() {
-        let instructions = "noop\naddx 3\naddx -5";
-        let instructions = parse_input(instructions);
+        let instruction_set = InstructionSet::device();
+        let instructions = parse_input(&instruction_set, "noop\naddx 3\naddx -5").unwrap();
         assert_eq!(
             instructions,
             vec![
-                Instruction::Noop,
-                Instruction::Addx(3),
-                Instruction::Addx(-5)
+                instruction_set.instruction("noop", 0).unwrap(),
+                instruction_set.instruction("addx", 3).unwrap(),
+                instruction_set.instruction("addx", -5).unwrap(),
             ]
         );
+
+        let error = parse_input(&instruction_set, "noop\nmulx 2").unwrap_err();
+        assert_eq!((error.line, error.column), (2, Some(1)));
     }
 
     #[test]
-    fn test_get_history() {
-        let mut history = BTreeMap::new();
-        history.insert(0, 1);
-        history.insert(2, 3);
-        history.insert(3, 4);
-        history.insert(6, 8);
-
-        assert_eq!(complete_history(history), vec![1, 1, 3, 4, 4, 4, 8]);
+    fn test_trace() {
+        let instruction_set = InstructionSet::device();
+        let instructions = parse_input(&instruction_set, "noop\naddx 3\naddx -5").unwrap();
+        let mut tracer = Tracer::new(&instruction_set);
+        let mut cpu = Cpu::new(&instruction_set);
+        cpu.run(&instructions, &mut tracer);
+
+        assert_eq!(cpu.cycle, 5);
+        assert_eq!(cpu.registers, vec![-1]);
+        assert_eq!(
+            tracer.lines[..5],
+            [
+                "Start cycle   1: begin executing noop",
+                "During cycle  1: X = 1",
+                "End of cycle  1: finish executing noop (X = 1)",
+                "Start cycle   2: begin executing addx 3",
+                "During cycle  2: X = 1",
+            ]
+        );
+        assert_eq!(
+            tracer.lines.last().unwrap(),
+            "End of cycle  5: finish executing addx -5 (X = -1)"
+        );
     }
 
     #[test]
-    fn test_get_signal_strength() {
-        let mut history = BTreeMap::new();
-        history.insert(19, 3);
-        history.insert(21, 5);
-        history.insert(40, 9);
-        history.insert(59, 23);
+    fn test_extended_instruction_set() {
+        // A second register, and a slower instruction
+        let mut instruction_set = InstructionSet::device();
+        instruction_set.registers.push(("Y", 10));
+        instruction_set.instructions.push(InstructionSpec {
+            name: "addy",
+            cycles: 3,
+            operation: Operation::Add(1),
+        });
+        let instructions = parse_input(&instruction_set, "addy 5\naddx 2\naddy -1").unwrap();
+
+        let mut cpu = Cpu::new(&instruction_set);
+        let mut signal = SignalStrength::new(4, 1);
+        cpu.run(&instructions, &mut signal);
+        assert_eq!(cpu.cycle, 8);
+        assert_eq!(cpu.registers, vec![3, 14]);
+        assert_eq!(signal.total, 4 + 5 + 6 * 3 + 7 * 3 + 8 * 3);
+    }
 
-        assert_eq!(get_signal_strengths(0, 20, history), vec![3 * 20, 40 * 5]);
+    #[test]
+    fn test_signal_strength() {
+        let instruction_set = InstructionSet::device();
+        let instructions = parse_input(&instruction_set, INPUT).unwrap();
+        let mut signal = SignalStrength::new(20, 40);
+        let mut crt = Crt::new(40);
+        Cpu::new(&instruction_set).run(&instructions, &mut (&mut signal, &mut crt));
+
+        assert_eq!(signal.total, 13140);
+        assert_eq!(crt.pixels.len(), 6);
+        assert_eq!(
+            format_drawing(&crt.pixels[..2]),
+            "\n##..##..##..##..##..##..##..##..##..##..\n###...###...###...###...###...###...###."
+        );
     }
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(puzzle_1(INPUT), "13140");
+        assert_eq!(puzzle_1(INPUT).unwrap(), "13140");
+    }
+
+    #[test]
+    fn test_puzzle_2() {
+        // The example does not draw letters, so the picture is returned
+        assert!(puzzle_2(INPUT)
+            .unwrap()
+            .starts_with("\n##..##..##..##..##..##..##..##..##..##..\n"));
     }
 }
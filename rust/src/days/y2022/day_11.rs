@@ -1,101 +1,308 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    error::Error,
+    fmt,
+};
+
+use itertools::Itertools;
+use num::BigUint;
+
+use crate::{
+    algorithms::{cycle::find_cycle, numtheory::lcm_all},
+    parsing::ParseError,
+    utils::is_verbose,
+};
+
+use types::{Monkey, WorryLevel, WORRY_MODULUS};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MonkeyError {
+    Parse(ParseError),
+    // The worry level overflows, becomes negative or is divided by zero
+    InvalidWorryLevel { monkey: usize, round: usize },
+}
+
+impl fmt::Display for MonkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MonkeyError::Parse(error) => write!(f, "{}", error),
+            MonkeyError::InvalidWorryLevel { monkey, round } => write!(
+                f,
+                "invalid worry level computed by monkey {} in round {}",
+                monkey, round
+            ),
+        }
+    }
+}
+
+impl Error for MonkeyError {}
+
+impl From<ParseError> for MonkeyError {
+    fn from(error: ParseError) -> Self {
+        MonkeyError::Parse(error)
+    }
+}
+
 mod types {
-    use std::collections::VecDeque;
+    use std::{collections::VecDeque, fmt, ops::Rem};
+
+    use num::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Zero};
+
+    use crate::algorithms::numtheory::Mod;
+
+    // Multiple of every divisibility test of the puzzle inputs, which are primes up to 23
+    pub const WORRY_MODULUS: u64 = 5354228880;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Operator {
+        Add,
+        Sub,
+        Mul,
+        Div,
+    }
+
+    impl Operator {
+        fn precedence(&self) -> u8 {
+            match self {
+                Operator::Add | Operator::Sub => 1,
+                Operator::Mul | Operator::Div => 2,
+            }
+        }
+
+        fn symbol(&self) -> char {
+            match self {
+                Operator::Add => '+',
+                Operator::Sub => '-',
+                Operator::Mul => '*',
+                Operator::Div => '/',
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Expression {
+        Old,
+        Number(u64),
+        Binary(Box<Expression>, Operator, Box<Expression>),
+    }
+
+    // Types that worry levels can be computed with
+    pub trait WorryLevel:
+        Clone
+        + From<u64>
+        + Zero
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + CheckedDiv
+        + Rem<u64, Output = Self>
+    {
+    }
+
+    impl<T> WorryLevel for T where
+        T: Clone
+            + From<u64>
+            + Zero
+            + CheckedAdd
+            + CheckedSub
+            + CheckedMul
+            + CheckedDiv
+            + Rem<u64, Output = T>
+    {
+    }
+
+    impl Expression {
+        // None on overflow, negative result or division by zero
+        pub fn evaluate<T: WorryLevel>(&self, old: &T) -> Option<T> {
+            match self {
+                Expression::Old => Some(old.clone()),
+                Expression::Number(n) => Some(T::from(*n)),
+                Expression::Binary(left, operator, right) => {
+                    let left = left.evaluate(old)?;
+                    let right = right.evaluate(old)?;
+                    match operator {
+                        Operator::Add => left.checked_add(&right),
+                        Operator::Sub => left.checked_sub(&right),
+                        Operator::Mul => left.checked_mul(&right),
+                        Operator::Div => left.checked_div(&right),
+                    }
+                }
+            }
+        }
+
+        // Result modulo M, None if the expression is not modular
+        pub fn evaluate_mod<const M: u64>(&self, old: Mod<M>) -> Option<Mod<M>> {
+            match self {
+                Expression::Old => Some(old),
+                Expression::Number(n) => Some(Mod::new(*n)),
+                Expression::Binary(left, operator, right) => {
+                    let left = left.evaluate_mod(old)?;
+                    let right = right.evaluate_mod(old)?;
+                    match operator {
+                        Operator::Add => Some(left + right),
+                        Operator::Mul => Some(left * right),
+                        Operator::Sub | Operator::Div => None,
+                    }
+                }
+            }
+        }
+
+        // True when the result modulo any number only depends on `old` modulo that number
+        pub fn is_modular(&self) -> bool {
+            match self {
+                Expression::Binary(left, Operator::Add | Operator::Mul, right) => {
+                    left.is_modular() && right.is_modular()
+                }
+                Expression::Binary(..) => false,
+                _ => true,
+            }
+        }
+
+        fn precedence(&self) -> u8 {
+            match self {
+                Expression::Binary(_, operator, _) => operator.precedence(),
+                _ => u8::MAX,
+            }
+        }
+    }
+
+    // Only the parentheses needed to keep the structure are written
+    impl fmt::Display for Expression {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Expression::Old => write!(f, "old"),
+                Expression::Number(n) => write!(f, "{}", n),
+                Expression::Binary(left, operator, right) => {
+                    let precedence = operator.precedence();
+                    match left.precedence() < precedence {
+                        true => write!(f, "({})", left)?,
+                        false => write!(f, "{}", left)?,
+                    }
+                    write!(f, " {} ", operator.symbol())?;
+                    match right.precedence() <= precedence {
+                        true => write!(f, "({})", right),
+                        false => write!(f, "{}", right),
+                    }
+                }
+            }
+        }
+    }
 
-    pub type Operation = dyn Fn(u64) -> Option<u64>;
+    #[derive(Debug, Clone, PartialEq, Eq)]
     pub struct Monkey {
         pub items: VecDeque<u64>,
-        pub operation: Box<Operation>,
+        pub operation: Expression,
         pub test_number: u64,
         pub recipient_success: usize,
         pub recipient_failed: usize,
     }
 
     impl Monkey {
-        pub fn play(&mut self, managed_worry: bool, divisor: u64) -> Option<(usize, u64)> {
-            let value = self.items.pop_front()?;
+        // Returns the recipient and the new worry level of an item, taken modulo
+        // `WORRY_MODULUS` when there is no relief
+        pub fn inspect(&self, value: u64, managed_worry: bool) -> Option<(usize, u64)> {
+            match managed_worry {
+                false => {
+                    let worry_level = self
+                        .operation
+                        .evaluate_mod(Mod::<WORRY_MODULUS>::new(value))?
+                        .value();
+                    match worry_level % self.test_number == 0 {
+                        true => Some((self.recipient_success, worry_level)),
+                        false => Some((self.recipient_failed, worry_level)),
+                    }
+                }
+                true => self.inspect_exact(&value, true),
+            }
+        }
 
+        // Same as `inspect`, without taking the worry level modulo anything
+        pub fn inspect_exact<T: WorryLevel>(
+            &self,
+            value: &T,
+            managed_worry: bool,
+        ) -> Option<(usize, T)> {
+            let worry_level = self.operation.evaluate(value)?;
             let worry_level = match managed_worry {
-                false => (self.operation)(value % divisor)?,
-                true => (self.operation)(value)? / 3,
+                false => worry_level,
+                true => worry_level.checked_div(&T::from(3))?,
             };
 
-            match worry_level % self.test_number == 0 {
+            match (worry_level.clone() % self.test_number).is_zero() {
                 true => Some((self.recipient_success, worry_level)),
                 false => Some((self.recipient_failed, worry_level)),
             }
         }
-
-        pub fn throw(&mut self, value: u64) {
-            self.items.push_back(value);
-        }
     }
 }
 
 mod parser {
-    use core::panic;
-    use std::num::ParseIntError;
-
     use nom::{
         branch::alt,
         bytes::complete::{tag, take_until},
-        character::complete::{self, anychar, digit1, multispace0, multispace1, newline},
-        multi::separated_list0,
-        sequence::{delimited, preceded, tuple},
+        character::complete::{self, char, multispace1, newline, space0},
+        combinator::{map, value},
+        multi::{fold_many0, separated_list0},
+        sequence::{delimited, pair, preceded, tuple},
         IResult,
     };
 
     pub use super::types::Monkey;
-
-    #[derive(Debug, Clone, Copy)]
-    enum Operand {
-        Old,
-        Number(u64),
-    }
-
-    fn map_operand(operand: &str) -> Result<Operand, ParseIntError> {
-        match operand {
-            "old" => Ok(Operand::Old),
-            string => {
-                let number = string.parse()?;
-                Ok(Operand::Number(number))
+    use super::types::{Expression, Operator};
+    #[cfg(test)]
+    use crate::algorithms::numtheory::Mod;
+    use crate::parsing::{blocks, column_of, parse_block, ParseError, ParseResult};
+
+    fn operator<'a>(
+        operators: &'static [(char, Operator)],
+    ) -> impl FnMut(&'a str) -> IResult<&'a str, Operator> {
+        move |input| {
+            let (rest, symbol) = delimited(space0, complete::anychar, space0)(input)?;
+            match operators.iter().find(|(c, _)| *c == symbol) {
+                Some(&(_, operator)) => Ok((rest, operator)),
+                None => Err(nom::Err::Error(nom::error::Error::new(
+                    input,
+                    nom::error::ErrorKind::Char,
+                ))),
             }
         }
     }
 
-    fn parse_operation(input: &str) -> IResult<&str, Box<super::types::Operation>> {
-        let (input, _) = tag("new = ")(input)?;
-        let (input, operand_1_str) = alt((tag("old"), digit1))(input)?;
-        let (input, operator_str) = delimited(multispace0, anychar, multispace0)(input)?;
-        let (input, operand_2_str) = alt((tag("old"), digit1))(input)?;
-
-        let operand_1 = map_operand(operand_1_str);
-        let operand_2 = map_operand(operand_2_str);
-
-        if operand_1.is_err() || operand_2.is_err() {
-            return Err(nom::Err::Failure(nom::error::Error {
-                input,
-                code: nom::error::ErrorKind::Digit,
-            }));
+    // Binary operations between operands, evaluated from left to right
+    fn binary<'a>(
+        operand: fn(&'a str) -> IResult<&'a str, Expression>,
+        operators: &'static [(char, Operator)],
+    ) -> impl FnMut(&'a str) -> IResult<&'a str, Expression> {
+        move |input| {
+            let (input, first) = operand(input)?;
+            fold_many0(
+                pair(operator(operators), operand),
+                move || first.clone(),
+                |left, (operator, right)| {
+                    Expression::Binary(Box::new(left), operator, Box::new(right))
+                },
+            )(input)
         }
+    }
 
-        let operand_1 = operand_1.unwrap();
-        let operand_2 = operand_2.unwrap();
+    fn factor(input: &str) -> IResult<&str, Expression> {
+        alt((
+            value(Expression::Old, tag("old")),
+            map(complete::u64, Expression::Number),
+            delimited(pair(char('('), space0), expression, pair(space0, char(')'))),
+        ))(input)
+    }
 
-        let operator = match operator_str {
-            '+' => u64::checked_add,
-            '-' => panic!(),
-            '*' => u64::checked_mul,
-            _ => panic!(),
-        };
+    fn term(input: &str) -> IResult<&str, Expression> {
+        binary(factor, &[('*', Operator::Mul), ('/', Operator::Div)])(input)
+    }
 
-        let operation: Box<dyn Fn(u64) -> Option<u64>> = match (operand_1, operand_2) {
-            (Operand::Old, Operand::Old) => Box::new(move |old| operator(old, old)),
-            (Operand::Old, Operand::Number(n2)) => Box::new(move |old| operator(old, n2)),
-            (Operand::Number(n1), Operand::Old) => Box::new(move |old| operator(n1, old)),
-            (Operand::Number(n1), Operand::Number(n2)) => Box::new(move |_| operator(n1, n2)),
-        };
+    pub fn expression(input: &str) -> IResult<&str, Expression> {
+        binary(term, &[('+', Operator::Add), ('-', Operator::Sub)])(input)
+    }
 
-        Ok((input, operation))
+    fn parse_operation(input: &str) -> IResult<&str, Expression> {
+        preceded(tag("new = "), expression)(input)
     }
 
     fn parse_test(input: &str) -> IResult<&str, (u64, usize, usize)> {
@@ -136,9 +343,66 @@ mod parser {
         ))
     }
 
-    pub fn parse_input(input: &str) -> Vec<Monkey> {
-        let (_, monkeys) = separated_list0(multispace1, parse_monkey)(input).unwrap();
-        monkeys
+    // Monkeys are numbered by their position in the input, and throw to the other ones
+    pub fn parse_input(input: &str) -> ParseResult<Vec<Monkey>> {
+        let blocks = blocks(input);
+        let monkeys = blocks
+            .iter()
+            .map(|&(first_line, block)| parse_block(first_line, block, "monkey", parse_monkey))
+            .collect::<ParseResult<Vec<_>>>()?;
+        if monkeys.is_empty() {
+            return Err(ParseError::new(1, "missing monkeys"));
+        }
+
+        for (i, (monkey, (first_line, block))) in monkeys.iter().zip(&blocks).enumerate() {
+            let recipients = [monkey.recipient_success, monkey.recipient_failed];
+            // The `If true` and `If false` lines follow the test
+            for (offset, (recipient, line)) in recipients
+                .into_iter()
+                .zip(block.lines().skip(4))
+                .enumerate()
+            {
+                let message = if recipient == i {
+                    format!("monkey {} throws to itself", i)
+                } else if recipient >= monkeys.len() {
+                    format!("there is no monkey {}", recipient)
+                } else {
+                    continue;
+                };
+                let number = line.rfind(' ').map_or(line, |space| &line[space + 1..]);
+                return Err(ParseError::at(
+                    first_line + 4 + offset,
+                    line,
+                    column_of(line, number),
+                    message,
+                ));
+            }
+        }
+        Ok(monkeys)
+    }
+
+    #[test]
+    fn test_expression() {
+        let (_, operation) = parse_operation("new = (old + 2) * old / 3 - 1").unwrap();
+        assert_eq!(operation.to_string(), "(old + 2) * old / 3 - 1");
+        assert_eq!(operation.evaluate(&4u64), Some(7));
+        assert_eq!(operation.evaluate(&0u64), None);
+
+        for text in ["old - (old - 1)", "old * (3 * old)", "(old) + 1", "old*old"] {
+            let (rest, parsed) = expression(text).unwrap();
+            assert_eq!(rest, "");
+            assert_eq!(expression(&parsed.to_string()), Ok(("", parsed)));
+        }
+        assert_eq!(expression("(old)+1").unwrap().1.to_string(), "old + 1");
+
+        assert!(expression("old * (old + 3)").unwrap().1.is_modular());
+        assert!(!expression("old * 2 - 1").unwrap().1.is_modular());
+        assert!(!expression("old / 2").unwrap().1.is_modular());
+
+        let (_, square) = expression("old * old + 1").unwrap();
+        let old = Mod::<{ u64::MAX }>::new(u64::MAX - 1);
+        assert_eq!(square.evaluate_mod(old), Some(Mod::new(2)));
+        assert_eq!(operation.evaluate_mod(Mod::<5>::new(4)), None);
     }
 
     #[test]
@@ -156,65 +420,240 @@ mod parser {
         assert_eq!(monkey.test_number, 19);
         assert_eq!(monkey.recipient_success, 2);
         assert_eq!(monkey.recipient_failed, 0);
+
+        let error =
+            parse_input(&format!("{}\n\nMonkey 2:\n  Starting items: 1, x", input)).unwrap_err();
+        assert_eq!((error.line, error.column), (9, Some(20)));
+        assert_eq!(error.expected.as_deref(), Some("monkey"));
+        let error = parse_input(&format!("{}\ngarbage", input)).unwrap_err();
+        assert_eq!(
+            (error.line, error.message.as_str()),
+            (6, "unexpected end of line")
+        );
+
+        let error = parse_input(input).unwrap_err();
+        assert_eq!((error.line, error.column), (5, Some(30)));
+        assert_eq!(error.message, "there is no monkey 2");
+        let self_throw = format!(
+            "{}\n\n{}",
+            input.replace("monkey 2", "monkey 1"),
+            input.replace("monkey 2", "monkey 0")
+        );
+        let error = parse_input(&self_throw).unwrap_err();
+        assert_eq!((error.line, error.column), (6, Some(31)));
+        assert_eq!(error.message, "monkey 0 throws to itself");
+        assert_eq!(parse_input("\n").unwrap_err().message, "missing monkeys");
     }
 }
 
-fn lcm(nums: &[u64]) -> u64 {
-    if nums.len() == 1 {
-        return nums[0];
+// Worry levels can be taken modulo this value without changing any divisibility test
+fn worry_modulus(monkeys: &[Monkey]) -> u64 {
+    lcm_all(monkeys.iter().map(|m| m.test_number))
+}
+
+// State of the monkeys at the end of a round
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RoundReport<T> {
+    round: usize,
+    items: Vec<Vec<T>>,
+    inspections: Vec<usize>,
+}
+
+// Same format as the examples of the puzzle
+impl<T: fmt::Display> fmt::Display for RoundReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "After round {}, the monkeys are holding items with these worry levels:",
+            self.round
+        )?;
+        for (monkey, items) in self.items.iter().enumerate() {
+            writeln!(f, "Monkey {}: {}", monkey, items.iter().join(", "))?;
+        }
+        write!(f, "\n== After round {} ==", self.round)?;
+        for (monkey, count) in self.inspections.iter().enumerate() {
+            write!(f, "\nMonkey {} inspected items {} times.", monkey, count)?;
+        }
+        Ok(())
     }
-    let a = nums[0];
-    let b = lcm(&nums[1..]);
-    a * b / gcd_of_two_numbers(a, b)
 }
 
-fn gcd_of_two_numbers(a: u64, b: u64) -> u64 {
-    if b == 0 {
-        return a;
+// Records the chosen rounds, and how many items each monkey threw to the others
+#[derive(Debug, Clone)]
+struct RoundObserver<T> {
+    rounds: BTreeSet<usize>,
+    reports: Vec<RoundReport<T>>,
+    transfers: BTreeMap<(usize, usize), usize>,
+}
+
+impl<T: Clone> RoundObserver<T> {
+    fn new(rounds: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            rounds: rounds.into_iter().collect(),
+            reports: vec![],
+            transfers: BTreeMap::new(),
+        }
+    }
+
+    fn throw(&mut self, from: usize, to: usize) {
+        *self.transfers.entry((from, to)).or_default() += 1;
+    }
+
+    fn end_round(&mut self, round: usize, items: &[VecDeque<T>], inspections: &[usize]) {
+        if self.rounds.contains(&round) {
+            self.reports.push(RoundReport {
+                round,
+                items: items.iter().map(|i| i.iter().cloned().collect()).collect(),
+                inspections: inspections.to_vec(),
+            });
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut edges = self
+            .transfers
+            .iter()
+            .map(|((from, to), count)| format!("  {} -> {} [label={}];", from, to, count));
+        format!("digraph monkeys {{\n{}\n}}", edges.join("\n"))
     }
-    gcd_of_two_numbers(b, a % b)
 }
 
-fn monkey_play(
+// Worry levels are taken modulo `modulus` when there is one, and are exact otherwise
+fn monkey_play<T: WorryLevel>(
     iterations: usize,
-    mut monkeys: Vec<types::Monkey>,
+    monkeys: &[Monkey],
     managed_worry: bool,
-) -> Vec<usize> {
+    modulus: Option<u64>,
+    mut observer: Option<&mut RoundObserver<T>>,
+) -> Result<Vec<usize>, MonkeyError> {
     let mut pass = vec![0; monkeys.len()];
-
-    let divisor = match managed_worry {
-        true => 3,
-        false => lcm(&monkeys.iter().map(|m| m.test_number).collect::<Vec<_>>()),
-    };
-
-    for _ in 0..iterations {
-        for i in 0..monkeys.len() {
-            while let Some(play) = monkeys[i].play(managed_worry, divisor) {
-                let (recipient, value) = play;
-                monkeys[recipient].throw(value);
+    let mut items: Vec<VecDeque<T>> = monkeys
+        .iter()
+        .map(|monkey| monkey.items.iter().map(|&value| value.into()).collect())
+        .collect();
+
+    for round in 1..=iterations {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            while let Some(value) = items[i].pop_front() {
+                let value = match modulus {
+                    Some(modulus) => value % modulus,
+                    None => value,
+                };
+                let (recipient, value) = monkey
+                    .inspect_exact(&value, managed_worry)
+                    .ok_or(MonkeyError::InvalidWorryLevel { monkey: i, round })?;
+                items[recipient].push_back(value);
                 pass[i] += 1;
+                if let Some(observer) = observer.as_mut() {
+                    observer.throw(i, recipient);
+                }
             }
         }
+        if let Some(observer) = observer.as_mut() {
+            observer.end_round(round, &items, &pass);
+        }
     }
 
-    pass
+    Ok(pass)
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let monkeys = parser::parse_input(input);
-    let mut pass = monkey_play(20, monkeys, true);
+// Follows a single item during one round, returns the inspections made by each monkey
+fn item_round(
+    monkeys: &[Monkey],
+    item: &mut (usize, u64),
+    managed_worry: bool,
+    round: usize,
+) -> Result<Vec<usize>, MonkeyError> {
+    let mut inspections = vec![0; monkeys.len()];
+    loop {
+        let (monkey, value) = *item;
+        inspections[monkey] += 1;
+        *item = monkeys[monkey]
+            .inspect(value, managed_worry)
+            .ok_or(MonkeyError::InvalidWorryLevel { monkey, round })?;
+        // Items thrown to a previous monkey wait for the next round
+        if item.0 < monkey {
+            return Ok(inspections);
+        }
+    }
+}
+
+// Same as `monkey_play`, but items are played independently until their state repeats.
+// Without relief, this needs worry levels reduced modulo the tests, so operations using
+// `-` or `/`, or tests not dividing `WORRY_MODULUS`, are played exactly instead.
+fn monkey_play_extrapolated(
+    iterations: usize,
+    monkeys: Vec<Monkey>,
+    managed_worry: bool,
+) -> Result<Vec<usize>, MonkeyError> {
+    let modulus = worry_modulus(&monkeys);
+    if !managed_worry
+        && (!WORRY_MODULUS.is_multiple_of(modulus)
+            || !monkeys.iter().all(|m| m.operation.is_modular()))
+    {
+        return monkey_play::<BigUint>(iterations, &monkeys, false, None, None);
+    }
+
+    let mut pass = vec![0; monkeys.len()];
+
+    let items = monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, monkey)| monkey.items.iter().map(move |&value| (i, value)));
+
+    for item in items {
+        let mut round = 0;
+        let trajectory = find_cycle(
+            item,
+            |item| {
+                round += 1;
+                item_round(&monkeys, item, managed_worry, round)
+            },
+            |&(monkey, value)| match managed_worry {
+                true => (monkey, value),
+                false => (monkey, value % modulus),
+            },
+            iterations,
+        );
+        if let Some(error) = trajectory.observations.iter().find_map(|o| o.clone().err()) {
+            return Err(error);
+        }
+        for (monkey, count) in pass.iter_mut().enumerate() {
+            *count += trajectory
+                .extrapolate(iterations, |inspections| {
+                    inspections
+                        .as_ref()
+                        .map_or(0, |inspections| inspections[monkey])
+                })
+                .unwrap();
+        }
+    }
+
+    Ok(pass)
+}
+
+pub fn puzzle_1(input: &str) -> Result<String, MonkeyError> {
+    let monkeys = parser::parse_input(input)?;
+    let mut observer = is_verbose().then(|| RoundObserver::new(1..=20));
+    let mut pass = monkey_play::<BigUint>(20, &monkeys, true, None, observer.as_mut())?;
+    if let Some(observer) = observer {
+        for report in &observer.reports {
+            println!("{}\n", report);
+        }
+        println!("{}", observer.to_dot());
+    }
     pass.sort();
 
-    (pass.pop().unwrap() * pass.pop().unwrap()).to_string()
+    Ok((pass.pop().unwrap() * pass.pop().unwrap()).to_string())
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let monkeys = parser::parse_input(input);
-    let mut pass = monkey_play(10000, monkeys, false);
+pub fn puzzle_2(input: &str) -> Result<String, MonkeyError> {
+    let monkeys = parser::parse_input(input)?;
+    let mut pass = monkey_play_extrapolated(10000, monkeys, false)?;
 
     pass.sort();
 
-    (pass.pop().unwrap() * pass.pop().unwrap()).to_string()
+    Ok((pass.pop().unwrap() * pass.pop().unwrap()).to_string())
 }
 
 #[cfg(test)]
@@ -251,11 +690,126 @@ Monkey 3:
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(puzzle_1(INPUT), "10605");
+        assert_eq!(puzzle_1(INPUT).unwrap(), "10605");
+    }
+
+    #[test]
+    fn test_monkey_play_extrapolated() {
+        let monkeys = parser::parse_input(INPUT).unwrap();
+        let modulus = worry_modulus(&monkeys);
+        for (iterations, managed_worry, modulus) in [(20, true, None), (1000, false, Some(modulus))]
+        {
+            assert_eq!(
+                monkey_play_extrapolated(iterations, monkeys.clone(), managed_worry).unwrap(),
+                monkey_play::<u64>(iterations, &monkeys, managed_worry, modulus, None).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_monkey_play_exact() {
+        let monkeys = parser::parse_input(INPUT).unwrap();
+        assert_eq!(
+            monkey_play::<BigUint>(12, &monkeys, false, None, None).unwrap(),
+            monkey_play::<u64>(12, &monkeys, false, Some(worry_modulus(&monkeys)), None).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_monkey_play_without_modulus() {
+        let monkeys = parser::parse_input(
+            "Monkey 0:
+  Starting items: 10, 21
+  Operation: new = old * 5 - 1
+  Test: divisible by 3
+    If true: throw to monkey 1
+    If false: throw to monkey 2
+
+Monkey 1:
+  Starting items: 7
+  Operation: new = old / 2 + 4
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 2
+
+Monkey 2:
+  Starting items: 5
+  Operation: new = old + 3
+  Test: divisible by 5
+    If true: throw to monkey 0
+    If false: throw to monkey 1",
+        )
+        .unwrap();
+        let modulus = worry_modulus(&monkeys);
+        let exact = monkey_play::<BigUint>(200, &monkeys, false, None, None).unwrap();
+
+        assert_eq!(
+            monkey_play_extrapolated(200, monkeys.clone(), false).unwrap(),
+            exact
+        );
+        assert_ne!(
+            monkey_play::<u64>(200, &monkeys, false, Some(modulus), None).unwrap(),
+            exact
+        );
+    }
+
+    #[test]
+    fn test_invalid_worry_level() {
+        let input = "Monkey 0:
+  Starting items: 1
+  Operation: new = old - 5
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 2
+  Operation: new = old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0";
+        let error = MonkeyError::InvalidWorryLevel {
+            monkey: 0,
+            round: 1,
+        };
+        assert_eq!(puzzle_1(input), Err(error.clone()));
+        assert_eq!(puzzle_2(input), Err(error));
+    }
+
+    #[test]
+    fn test_round_observer() {
+        let monkeys = parser::parse_input(INPUT).unwrap();
+        let mut observer = RoundObserver::new([1, 20]);
+        monkey_play::<u64>(20, &monkeys, true, None, Some(&mut observer)).unwrap();
+
+        assert_eq!(
+            observer.reports[0].to_string(),
+            "After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2: 
+Monkey 3: 
+
+== After round 1 ==
+Monkey 0 inspected items 2 times.
+Monkey 1 inspected items 4 times.
+Monkey 2 inspected items 3 times.
+Monkey 3 inspected items 5 times."
+        );
+        assert_eq!(observer.reports[1].round, 20);
+        assert_eq!(observer.reports[1].inspections, vec![101, 95, 7, 105]);
+
+        // Every inspection ends with a throw
+        let dot = observer.to_dot();
+        assert!(dot.starts_with("digraph monkeys {\n  0 -> 3 [label="));
+        assert_eq!(
+            observer.transfers.values().sum::<usize>(),
+            101 + 95 + 7 + 105
+        );
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(puzzle_2(INPUT), "2713310158");
+        assert_eq!(puzzle_2(INPUT).unwrap(), "2713310158");
     }
 }
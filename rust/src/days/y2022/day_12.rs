@@ -1,66 +1,19 @@
-use std::{
-    cmp::Ordering,
-    collections::{BTreeMap, BTreeSet},
-};
+use std::collections::BTreeMap;
 
-use crate::structs::geometry::Point2;
+use crate::{
+    algorithms::search::{bfs, Neighbours},
+    structs::geometry::{Direction4, Point2, Rect},
+};
 
 type Point = Point2<u16>;
 
-#[derive(Debug, Eq)]
-struct TreeNode {
-    position: Point,
-    path: Vec<Point>,
-    children: Vec<TreeNode>,
-}
+struct HeightMap(BTreeMap<Point, char>);
 
-impl Point {
-    fn shift_left(self) -> Self {
-        Self {
-            x: self.x.saturating_sub(1),
-            y: self.y,
-        }
-    }
-    fn shift_up(self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y.saturating_sub(1),
-        }
-    }
-    fn shift_right(self) -> Self {
-        Self {
-            x: self.x.saturating_add(1),
-            y: self.y,
-        }
-    }
-    fn shift_down(self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y.saturating_add(1),
-        }
-    }
-}
-
-impl Ord for TreeNode {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
-    }
-}
+impl Neighbours for HeightMap {
+    type Node = Point;
 
-impl PartialOrd for TreeNode {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(
-            self.path
-                .len()
-                .cmp(&other.path.len())
-                .then(self.position.cmp(&other.position)),
-        )
-    }
-}
-
-impl PartialEq for TreeNode {
-    fn eq(&self, other: &Self) -> bool {
-        self.path == other.path
+    fn neighbours(&self, node: &Point) -> impl Iterator<Item = Point> {
+        find_accesible_squares(&self.0, *node).into_iter()
     }
 }
 
@@ -99,12 +52,17 @@ fn find_accesible_squares(
 
     let &current_height = height_map.get(&current_position).unwrap();
 
+    let bounds = Rect::max_bounds();
+
     for potential_position in [
-        current_position.shift_left(),
-        current_position.shift_up(),
-        current_position.shift_right(),
-        current_position.shift_down(),
-    ] {
+        Direction4::Left,
+        Direction4::Up,
+        Direction4::Right,
+        Direction4::Down,
+    ]
+    .into_iter()
+    .filter_map(|direction| current_position.checked_step_y_down(direction, &bounds))
+    {
         if let Some(&height) = height_map.get(&potential_position) {
             let height_diff = height as i8 - current_height as i8;
             if height_diff < 2 {
@@ -116,97 +74,36 @@ fn find_accesible_squares(
     accessible_squares
 }
 
+// Length of the shortest path, starting from any square at height 'a' when no start is given
 fn find_solution(
     height_map: BTreeMap<Point, char>,
     start: Option<Point>,
     end: Point,
-) -> Vec<Point> {
-    let mut visited_positions = BTreeMap::new();
-
-    // The ordering uses position and path, which are not modified
-    #[allow(clippy::mutable_key_type)]
-    let mut queue = BTreeSet::new();
-
-    let mut start_condidates = match start {
-        Some(start) => vec![TreeNode {
-            position: start,
-            path: vec![],
-            children: vec![],
-        }],
+) -> Option<usize> {
+    let starts = match start {
+        Some(start) => vec![start],
         None => height_map
             .iter()
             .filter(|&(_, c)| *c == 'a')
-            .map(|(position, _)| TreeNode {
-                position: *position,
-                path: vec![],
-                children: vec![],
-            })
+            .map(|(position, _)| *position)
             .collect(),
     };
 
-    for node in &mut start_condidates {
-        queue.insert(node);
-    }
-
-    let mut results = vec![];
-
-    while let Some(node) = queue.pop_first() {
-        let accessible_positions = find_accesible_squares(&height_map, node.position);
-
-        for position in accessible_positions {
-            if position == node.position {
-                continue;
-            }
-
-            let mut path = node.path.clone();
-            path.push(position);
-
-            if let Some(path_length) = visited_positions.get_mut(&position) {
-                if *path_length <= path.len() {
-                    continue;
-                } else {
-                    *path_length = path.len();
-                }
-            } else {
-                visited_positions.insert(position, path.len());
-            }
-
-            let child = TreeNode {
-                position,
-                path,
-                children: vec![],
-            };
-
-            if position == end {
-                results.push(child);
-                break;
-            }
-            node.children.push(child);
-        }
-        for child in &mut node.children {
-            queue.insert(child);
-        }
-    }
-
-    results.sort_by_key(|node| node.path.len());
-    if let Some(path) = results.first() {
-        return path.path.clone();
-    }
-    vec![]
+    bfs(&HeightMap(height_map), starts, |&position| position == end).map(|r| r.distance)
 }
 
 pub fn puzzle_1(input: &str) -> String {
     let (start, goal, height_map) = parse_input(input);
     let solution = find_solution(height_map, Some(start), goal);
 
-    solution.len().to_string()
+    solution.unwrap().to_string()
 }
 
 pub fn puzzle_2(input: &str) -> String {
     let (_, goal, height_map) = parse_input(input);
     let solution = find_solution(height_map, None, goal);
 
-    solution.len().to_string()
+    solution.unwrap().to_string()
 }
 
 #[cfg(test)]
@@ -1,48 +1,143 @@
-use std::collections::BTreeSet;
+use std::{cmp::Ordering, fmt, slice, str::FromStr};
 
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{self, newline},
-    combinator::map,
-    multi::{separated_list0, separated_list1},
-    sequence::{delimited, pair, separated_pair},
+    character::complete::{digit1, multispace0, newline},
+    combinator::{map, map_res},
+    error::{Error, ErrorKind},
+    multi::separated_list0,
+    sequence::{delimited, separated_pair},
     IResult,
 };
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-enum ListElement {
-    Number(u8),
-    List(Vec<ListElement>),
-}
+use crate::{
+    parsing::{blocks, parse_block, parse_line, ParseError, ParseResult},
+    utils::is_verbose,
+};
 
-impl Ord for ListElement {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
-    }
+// Numbers can be of any integer type, including big integers
+#[derive(Debug, Clone)]
+enum ListElement<N = u64> {
+    Number(N),
+    List(Vec<ListElement<N>>),
 }
 
-impl PartialOrd for ListElement {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl<N: Ord> Ord for ListElement<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (ListElement::Number(a), ListElement::Number(b)) => a.partial_cmp(b),
-            (ListElement::List(list_a), ListElement::List(list_b)) => list_a.partial_cmp(list_b),
-            (ListElement::Number(a), ListElement::List(list_b)) => {
-                vec![ListElement::Number(*a)].partial_cmp(list_b)
+            (ListElement::Number(a), ListElement::Number(b)) => a.cmp(b),
+            (ListElement::List(list_a), ListElement::List(list_b)) => list_a.cmp(list_b),
+            (ListElement::Number(_), ListElement::List(list_b)) => {
+                slice::from_ref(self).cmp(list_b.as_slice())
             }
-            (ListElement::List(list_a), ListElement::Number(b)) => {
-                list_a.partial_cmp(&vec![ListElement::Number(*b)])
+            (ListElement::List(list_a), ListElement::Number(_)) => {
+                list_a.as_slice().cmp(slice::from_ref(other))
             }
         }
     }
 }
 
-fn parse_input_list(input: &str) -> IResult<&str, ListElement> {
+// Packets are equal when they are in the same position of the order, like `[2]` and `[[2]]`
+impl<N: Ord> PartialEq for ListElement<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N: Ord> Eq for ListElement<N> {}
+
+impl<N: Ord> PartialOrd for ListElement<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N> From<N> for ListElement<N> {
+    fn from(number: N) -> Self {
+        ListElement::Number(number)
+    }
+}
+
+impl<N> From<Vec<ListElement<N>>> for ListElement<N> {
+    fn from(list: Vec<ListElement<N>>) -> Self {
+        ListElement::List(list)
+    }
+}
+
+// Same format as the input, or JSON with spaces after the commas with `{:#}`
+impl<N: fmt::Display> fmt::Display for ListElement<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ListElement::Number(number) => write!(f, "{}", number),
+            ListElement::List(list) => {
+                let separator = match f.alternate() {
+                    true => ", ",
+                    false => ",",
+                };
+                write!(f, "[")?;
+                for (i, element) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    element.fmt(f)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+// JSON arrays of integers, whitespace being allowed between the tokens
+impl<N: FromStr> FromStr for ListElement<N> {
+    type Err = ParseError;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        parse_line(1, json, delimited(multispace0, json_list, multispace0))
+            .map_err(|e| e.expecting("JSON array"))
+    }
+}
+
+fn number<N: FromStr>(input: &str) -> IResult<&str, ListElement<N>> {
+    map(map_res(digit1, str::parse), ListElement::Number)(input)
+}
+
+// Deeper packets are rejected rather than overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+fn too_deep<T>(input: &str) -> IResult<&str, T> {
+    Err(nom::Err::Failure(Error::new(input, ErrorKind::TooLarge)))
+}
+
+fn packet<N: FromStr>(input: &str) -> IResult<&str, ListElement<N>> {
+    nested_packet(input, 0)
+}
+
+fn nested_packet<N: FromStr>(input: &str, depth: usize) -> IResult<&str, ListElement<N>> {
+    if depth == MAX_DEPTH {
+        return too_deep(input);
+    }
+    let element = |input| alt((number, |input| nested_packet(input, depth + 1)))(input);
+    let (input, result) = delimited(tag("["), separated_list0(tag(","), element), tag("]"))(input)?;
+
+    Ok((input, ListElement::List(result)))
+}
+
+fn json_list<N: FromStr>(input: &str) -> IResult<&str, ListElement<N>> {
+    nested_json_list(input, 0)
+}
+
+fn nested_json_list<N: FromStr>(input: &str, depth: usize) -> IResult<&str, ListElement<N>> {
+    if depth == MAX_DEPTH {
+        return too_deep(input);
+    }
+    let element = |input| alt((number, |input| nested_json_list(input, depth + 1)))(input);
     let (input, result) = delimited(
         tag("["),
-        separated_list0(
-            tag(","),
-            alt((map(complete::u8, ListElement::Number), parse_input_list)),
+        delimited(
+            multispace0,
+            separated_list0(delimited(multispace0, tag(","), multispace0), element),
+            multispace0,
         ),
         tag("]"),
     )(input)?;
@@ -50,18 +145,31 @@ fn parse_input_list(input: &str) -> IResult<&str, ListElement> {
     Ok((input, ListElement::List(result)))
 }
 
-fn parse_input(input: &str) -> Vec<(ListElement, ListElement)> {
-    let (_, result) = separated_list1(
-        pair(newline, newline),
-        separated_pair(parse_input_list, newline, parse_input_list),
-    )(input)
-    .unwrap();
+fn parse_input_list(input: &str) -> IResult<&str, ListElement> {
+    packet(input)
+}
+
+fn parse_input(input: &str) -> ParseResult<Vec<(ListElement, ListElement)>> {
+    blocks(input)
+        .into_iter()
+        .map(|(first_line, block)| {
+            parse_block(
+                first_line,
+                block,
+                "pair of packets",
+                separated_pair(parse_input_list, newline, parse_input_list),
+            )
+        })
+        .collect()
+}
 
-    result
+// The sort is stable, so equal packets are all kept in their original order
+fn sort_packets<N: Ord>(packets: &mut [ListElement<N>]) {
+    packets.sort();
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let pairs = parse_input(input);
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    let pairs = parse_input(input)?;
 
     let sum: usize = pairs
         .iter()
@@ -73,25 +181,42 @@ pub fn puzzle_1(input: &str) -> String {
         })
         .sum();
 
-    sum.to_string()
+    Ok(sum.to_string())
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let input = parse_input(input);
-    let mut packets = input
-        .iter()
-        .flat_map(|(a, b)| vec![a, b])
-        .collect::<BTreeSet<_>>();
-    let (_, divider_1) = parse_input_list("[[2]]").unwrap();
-    let (_, divider_2) = parse_input_list("[[6]]").unwrap();
-
-    packets.insert(&divider_1);
-    packets.insert(&divider_2);
+pub fn puzzle_2(input: &str) -> ParseResult<String> {
+    let pairs = parse_input(input)?;
+    let packets: Vec<_> = pairs.into_iter().flat_map(|(a, b)| [a, b]).collect();
+    let divider_1: ListElement = "[[2]]".parse().unwrap();
+    let divider_2: ListElement = "[[6]]".parse().unwrap();
+
+    // Positions in the sorted packets, the first divider being before the second one
+    let index_1 = packets.iter().filter(|&p| p < &divider_1).count() + 1;
+    let index_2 = packets.iter().filter(|&p| p < &divider_2).count() + 2;
+
+    if is_verbose() {
+        let mut sorted = packets;
+        sorted.extend([divider_1, divider_2]);
+        sort_packets(&mut sorted);
+        for packet in sorted {
+            println!("{}", packet);
+        }
+    }
 
-    let index_1 = packets.iter().position(|&el| el == &divider_1).unwrap() + 1;
-    let index_2 = packets.iter().position(|&el| el == &divider_2).unwrap() + 1;
+    Ok((index_1 * index_2).to_string())
+}
 
-    (index_1 * index_2).to_string()
+// Any input is either parsed into a packet that prints back stably, or rejected. Shared
+// by the tests and the `parse_input_list` fuzz target.
+#[cfg(any(test, fuzzing))]
+pub(crate) fn check_parse_input_list(data: &[u8]) {
+    let text = String::from_utf8_lossy(data);
+    if let Ok((_, packet)) = parse_input_list(&text) {
+        let printed = packet.to_string();
+        let (_, reparsed) = parse_input_list(&printed).unwrap();
+        assert_eq!(reparsed.to_string(), printed, "{:?}", text);
+    }
+    let _ = text.parse::<ListElement>();
 }
 
 #[cfg(test)]
@@ -122,69 +247,236 @@ mod tests {
 
     use super::*;
 
+    // Packets such as `[[1]]` and `[1]` are equal, so trees are compared through their text
+    fn assert_same_tree(a: &ListElement, b: &ListElement) {
+        assert_eq!(a.to_string(), b.to_string());
+    }
+
     #[test]
     fn test_can_parse_list() {
         use ListElement::*;
 
         let input = "[[4,4],4,4]";
         let (_, result) = parse_input_list(input).unwrap();
-        assert_eq!(
-            result,
-            List(vec![List(vec![Number(4), Number(4)]), Number(4), Number(4)])
+        assert_same_tree(
+            &result,
+            &List(vec![List(vec![Number(4), Number(4)]), Number(4), Number(4)]),
         );
 
         let input = "[]";
         let (_, result) = parse_input_list(input).unwrap();
-        assert_eq!(result, List(vec![]));
+        assert_same_tree(&result, &List(vec![]));
     }
 
     #[test]
     fn test_can_parse_input() {
-        let result = parse_input(INPUT);
+        let result = parse_input(INPUT).unwrap();
 
         assert_eq!(result.len(), 8);
     }
 
+    #[test]
+    fn test_parse_error() {
+        let error = parse_input(&INPUT.replace("[[4,4],4,4,4]", "[[4,4],4,4,4")).unwrap_err();
+
+        assert_eq!((error.line, error.column), (11, Some(13)));
+        assert_eq!(error.expected.as_deref(), Some("pair of packets"));
+    }
+
     #[test]
     fn test_orderings() {
         let input = "[1,1,3,1,1]\n[1,1,5,1,1]";
-        let (a, b) = parse_input(input).first().unwrap().clone();
+        let (a, b) = parse_input(input).unwrap()[0].clone();
 
         assert!(a < b);
 
         let input = "[[1],[2,3,4]]\n[[1],4]";
-        let (a, b) = parse_input(input).first().unwrap().clone();
+        let (a, b) = parse_input(input).unwrap()[0].clone();
 
         assert!(a < b);
 
         let input = "[9]\n[[8,7,6]]";
-        let (a, b) = parse_input(input).first().unwrap().clone();
+        let (a, b) = parse_input(input).unwrap()[0].clone();
 
         assert!(a > b);
 
         let input = "[[4,4],4,4]\n[[4,4],4,4,4]";
-        let (a, b) = parse_input(input).first().unwrap().clone();
+        let (a, b) = parse_input(input).unwrap()[0].clone();
 
         assert!(a < b);
 
         let input = "[7,7,7,7]\n[7,7,7]";
-        let (a, b) = parse_input(input).first().unwrap().clone();
+        let (a, b) = parse_input(input).unwrap()[0].clone();
 
         assert!(a > b);
 
         let input = "[1,[2,[3,[4,[5,6,7]]]],8,9]\n[1,[2,[3,[4,[5,6,0]]]],8,9]";
-        let (a, b) = parse_input(input).first().unwrap().clone();
+        let (a, b) = parse_input(input).unwrap()[0].clone();
 
         assert!(a > b);
     }
 
+    #[test]
+    fn test_display() {
+        let packets = parse_input(INPUT).unwrap();
+        for packet in packets.iter().flat_map(|(a, b)| [a, b]) {
+            let text = packet.to_string();
+            let (rest, parsed) = parse_input_list(&text).unwrap();
+            assert_eq!(rest, "");
+            assert_same_tree(&parsed, packet);
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        use ListElement::*;
+
+        let packet: ListElement = " [1, [ 2,3] ,[]]\n".parse().unwrap();
+        assert_same_tree(
+            &packet,
+            &ListElement::from(vec![
+                1.into(),
+                vec![2.into(), 3.into()].into(),
+                List(vec![]),
+            ]),
+        );
+        assert_eq!(format!("{:#}", packet), "[1, [2, 3], []]");
+        assert_eq!(packet.to_string(), "[1,[2,3],[]]");
+
+        let error = "[1, [2]".parse::<ListElement>().unwrap_err();
+        assert_eq!(error.expected.as_deref(), Some("JSON array"));
+        assert!("[1.5]".parse::<ListElement>().is_err());
+    }
+
+    #[test]
+    fn test_any_width() {
+        use num::BigUint;
+
+        let (_, small) = packet::<BigUint>("[[18446744073709551615]]").unwrap();
+        let (_, big) = packet::<BigUint>("[18446744073709551616,0]").unwrap();
+        assert!(small < big);
+        assert_eq!(big.to_string(), "[18446744073709551616,0]");
+        assert!(parse_input_list("[18446744073709551616]").is_err());
+    }
+
+    #[test]
+    fn test_sort_keeps_duplicates() {
+        let mut packets: Vec<ListElement> = ["[2]", "[[1]]", "[[2]]", "[1]", "[2]"]
+            .iter()
+            .map(|p| p.parse().unwrap())
+            .collect();
+        sort_packets(&mut packets);
+        let sorted: Vec<_> = packets.iter().map(|p| p.to_string()).collect();
+        assert_eq!(sorted, ["[[1]]", "[1]", "[2]", "[[2]]", "[2]"]);
+    }
+
+    // Splitmix64, seeded so that failures can be replayed
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    // Small numbers and short lists, so that many packets compare equal
+    fn random_list(random: &mut Random, depth: usize) -> ListElement {
+        let length = random.below(4);
+        let elements = (0..length)
+            .map(|_| match depth > 0 && random.below(3) == 0 {
+                true => random_list(random, depth - 1),
+                false => ListElement::Number(random.below(4)),
+            })
+            .collect();
+        ListElement::List(elements)
+    }
+
+    #[test]
+    fn test_total_order() {
+        let mut random = Random(13);
+        for _ in 0..2000 {
+            let [a, b, c] = [(); 3].map(|_| random_list(&mut random, 3));
+            assert_eq!(a.cmp(&a), Ordering::Equal, "{}", a);
+            assert_eq!(a.cmp(&b), b.cmp(&a).reverse(), "{} {}", a, b);
+            assert_eq!(a == b, a.cmp(&b) == Ordering::Equal, "{} {}", a, b);
+            if a <= b && b <= c {
+                assert!(a <= c, "{} {} {}", a, b, c);
+            }
+            if a < b && b <= c {
+                assert!(a < c, "{} {} {}", a, b, c);
+            }
+        }
+
+        let mut packets: Vec<_> = (0..200).map(|_| random_list(&mut random, 3)).collect();
+        sort_packets(&mut packets);
+        for (i, a) in packets.iter().enumerate() {
+            for b in &packets[i..] {
+                assert!(a <= b, "{} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_serializer_round_trip() {
+        let mut random = Random(42);
+        for _ in 0..2000 {
+            let packet = random_list(&mut random, 5);
+            let text = packet.to_string();
+            let (rest, parsed) = parse_input_list(&text).unwrap();
+            assert_eq!((rest, parsed.to_string()), ("", text.clone()));
+
+            let json: ListElement = format!("{:#}", packet).parse().unwrap();
+            assert_eq!(json.to_string(), text);
+        }
+    }
+
+    // Random bytes and mutated packets, from a seeded generator so that failures replay
+    #[test]
+    fn test_parse_random_input() {
+        let mut random = Random(7);
+        let alphabet = b"[],0123456789 \n-";
+        for _ in 0..5000 {
+            let length = random.below(40) as usize;
+            let bytes: Vec<u8> = (0..length).map(|_| random.next() as u8).collect();
+            check_parse_input_list(&bytes);
+
+            let bytes: Vec<u8> = (0..length)
+                .map(|_| alphabet[random.below(alphabet.len() as u64) as usize])
+                .collect();
+            check_parse_input_list(&bytes);
+
+            // Valid packets with a byte changed, removed or inserted
+            let mut bytes = random_list(&mut random, 4).to_string().into_bytes();
+            let position = random.below(bytes.len() as u64) as usize;
+            let byte = alphabet[random.below(alphabet.len() as u64) as usize];
+            match random.below(3) {
+                0 => bytes[position] = byte,
+                1 => _ = bytes.remove(position),
+                _ => bytes.insert(position, byte),
+            }
+            check_parse_input_list(&bytes);
+        }
+
+        check_parse_input_list("[".repeat(100_000).as_bytes());
+        check_parse_input_list(format!("[{}]", "9".repeat(1000)).as_bytes());
+        assert!(parse_input_list(&"[".repeat(MAX_DEPTH + 1)).is_err());
+    }
+
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(puzzle_1(INPUT), "13");
+        assert_eq!(puzzle_1(INPUT).unwrap(), "13");
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(puzzle_2(INPUT), "140");
+        assert_eq!(puzzle_2(INPUT).unwrap(), "140");
     }
 }
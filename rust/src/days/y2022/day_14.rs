@@ -1,143 +1,313 @@
-use std::ops::RangeInclusive;
+use std::iter;
 
 use nom::{
-    bytes::complete::tag, character::complete::newline, combinator::map, multi::separated_list1,
-    sequence::separated_pair,
+    bytes::complete::tag, character::complete, combinator::map, multi::separated_list1,
+    sequence::separated_pair, IResult,
 };
 
-use crate::structs::geometry::Point2;
+use crate::{
+    algorithms::simulation::{Driver, Simulation},
+    parsing::{parse_lines, ParseResult},
+    render::{self, Color, Image},
+    structs::geometry::{point2, Line2, Point2, Rect},
+    utils::is_verbose,
+};
+
+type Point = Point2<i32>;
 
-type Point = Point2<usize>;
+const SOURCE: Point = point2!(500, 0);
 
-#[derive(Debug)]
-struct Map {
-    bottom_height: usize,
-    offset_x: usize,
-    data: Vec<Vec<bool>>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Air,
+    Rock,
+    Sand,
 }
 
-impl Map {
-    fn set_occupied(&mut self, point: Point) {
-        let data_x = point.x - self.offset_x;
-        self.data[point.y][data_x] = true;
-    }
-    fn is_occupied(&self, point: Point) -> bool {
-        let data_x = point.x - self.offset_x;
-        self.data[point.y][data_x]
-    }
+#[derive(Debug, Clone)]
+struct Cave {
+    // Allocated cells, the grid grows when a cell outside of it is set
+    origin: Point,
+    cells: Vec<Vec<Cell>>,
+    // Smallest rectangle containing all the rocks and sand
+    bounds: Option<Rect<i32>>,
+    lowest_rock: i32,
 }
 
-fn get_range(start: usize, finish: usize) -> RangeInclusive<usize> {
-    match start < finish {
-        true => start..=finish,
-        false => finish..=start,
+impl Cave {
+    fn new() -> Self {
+        Self {
+            origin: SOURCE,
+            cells: vec![],
+            bounds: None,
+            lowest_rock: i32::MIN,
+        }
     }
-}
 
-fn parse_input(input: &str) -> Map {
-    let coord_parser = map(
-        separated_pair(
-            nom::character::complete::u16::<&str, ()>,
-            tag(","),
-            nom::character::complete::u16::<&str, ()>,
-        ),
-        |(x, y)| (x as usize, y as usize),
-    );
+    fn get(&self, point: Point) -> Cell {
+        let (x, y) = (point.x - self.origin.x, point.y - self.origin.y);
+        if x < 0 || y < 0 {
+            return Cell::Air;
+        }
+        self.cells
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(Cell::Air)
+    }
 
-    let mut parser = separated_list1(newline, separated_list1(tag(" -> "), coord_parser));
+    // The grid at least doubles in the directions where it grows
+    fn reserve(&mut self, point: Point) {
+        if self.cells.is_empty() {
+            self.origin = point;
+            self.cells = vec![vec![Cell::Air]];
+        }
+        let width = self.cells[0].len() as i32;
+        let height = self.cells.len() as i32;
 
-    let (_, result) = parser(input).unwrap();
+        if point.x < self.origin.x {
+            let extra = (self.origin.x - point.x).max(width);
+            for row in &mut self.cells {
+                row.splice(0..0, iter::repeat_n(Cell::Air, extra as usize));
+            }
+            self.origin.x -= extra;
+        }
+        if point.x >= self.origin.x + self.cells[0].len() as i32 {
+            let extra = (point.x - self.origin.x - self.cells[0].len() as i32 + 1).max(width);
+            for row in &mut self.cells {
+                row.extend(iter::repeat_n(Cell::Air, extra as usize));
+            }
+        }
 
-    let max_y = result.iter().flatten().max_by_key(|c| c.1).unwrap().1 + 1;
+        let width = self.cells[0].len();
+        if point.y < self.origin.y {
+            let extra = (self.origin.y - point.y).max(height);
+            self.cells
+                .splice(0..0, iter::repeat_n(vec![Cell::Air; width], extra as usize));
+            self.origin.y -= extra;
+        }
+        if point.y >= self.origin.y + self.cells.len() as i32 {
+            let extra = (point.y - self.origin.y - self.cells.len() as i32 + 1).max(height);
+            self.cells
+                .extend(iter::repeat_n(vec![Cell::Air; width], extra as usize));
+        }
+    }
 
-    let bottom_height = max_y + 1;
-    let offset_x = 500 - bottom_height;
+    fn set(&mut self, point: Point, cell: Cell) {
+        self.reserve(point);
+        let (x, y) = (point.x - self.origin.x, point.y - self.origin.y);
+        self.cells[y as usize][x as usize] = cell;
 
-    let mut data: Vec<Vec<bool>> = vec![];
-    data.reserve(bottom_height + 1);
+        self.bounds = Some(match self.bounds {
+            None => Rect::new(point, point),
+            Some(bounds) => Rect::new(
+                point2!(bounds.min.x.min(point.x), bounds.min.y.min(point.y)),
+                point2!(bounds.max.x.max(point.x), bounds.max.y.max(point.y)),
+            ),
+        });
+        if cell == Cell::Rock {
+            self.lowest_rock = self.lowest_rock.max(point.y);
+        }
+    }
 
-    for y in 0..=bottom_height {
-        data.insert(y, vec![false; 2 * bottom_height + 1]);
+    fn add_rock_path(&mut self, path: &[Point]) {
+        if let [point] = path {
+            self.set(*point, Cell::Rock);
+        }
+        for segment in path.windows(2) {
+            let line = Line2 {
+                start: segment[0],
+                end: segment[1],
+            };
+            for point in &line {
+                self.set(point, Cell::Rock);
+            }
+        }
     }
+}
 
-    let mut output_map = Map {
-        bottom_height,
-        offset_x,
-        data,
-    };
+fn rock_path(input: &str) -> IResult<&str, Vec<Point>> {
+    let point = map(
+        separated_pair(complete::i32, tag(","), complete::i32),
+        |(x, y)| point2!(x, y),
+    );
+    separated_list1(tag(" -> "), point)(input)
+}
 
-    for path in result {
-        path.windows(2).for_each(|segment| {
-            let start = segment[0];
-            let finish = segment[1];
-            if start.0 != finish.0 {
-                for x in get_range(start.0, finish.0) {
-                    output_map.set_occupied(Point { x, y: start.1 });
-                }
-                return;
-            }
-            if start.1 != finish.1 {
-                for y in get_range(start.1, finish.1) {
-                    output_map.set_occupied(Point { x: start.0, y });
-                }
-            }
-        });
+fn parse_input(input: &str) -> ParseResult<Cave> {
+    let paths = parse_lines(input, rock_path).map_err(|e| e.expecting("rock path"))?;
+    let mut cave = Cave::new();
+    for path in paths {
+        cave.add_rock_path(&path);
     }
+    Ok(cave)
+}
 
-    output_map
+// Each step pours a grain of sand, observing where it came to rest
+#[derive(Debug, Clone)]
+struct Sand {
+    cave: Cave,
+    source: Point,
+    // Infinite floor, two rows below the lowest rock
+    floor: Option<i32>,
+    // Cells the previous grain fell through, the next grain follows the same way
+    path: Vec<Point>,
+    resting_grains: usize,
+    is_full: bool,
 }
 
-fn sand_fall(map: &Map, point: Point) -> Point {
-    if !map.is_occupied(point + Point::unit_y()) {
-        return point + Point::unit_y();
+impl Sand {
+    fn new(cave: Cave, source: Point, has_floor: bool) -> Self {
+        let floor = has_floor.then_some(cave.lowest_rock.max(source.y) + 2);
+        Self {
+            cave,
+            source,
+            floor,
+            path: vec![],
+            resting_grains: 0,
+            is_full: false,
+        }
     }
-    if !map.is_occupied(point + Point::unit_y() - Point::unit_x()) {
-        return point + Point::unit_y() - Point::unit_x();
+
+    fn is_free(&self, point: Point) -> bool {
+        self.floor != Some(point.y) && self.cave.get(point) == Cell::Air
+    }
+
+    fn fall(&self, point: Point) -> Option<Point> {
+        [0, -1, 1]
+            .into_iter()
+            .map(|dx| point2!(point.x + dx, point.y + 1))
+            .find(|&next| self.is_free(next))
+    }
+
+    // Rows of the bounding box of the cave, the floor and the source
+    fn rows(&self) -> Vec<Vec<char>> {
+        let mut bounds = self
+            .cave
+            .bounds
+            .unwrap_or(Rect::new(self.source, self.source));
+        bounds.min.x = bounds.min.x.min(self.source.x);
+        bounds.max.x = bounds.max.x.max(self.source.x);
+        bounds.min.y = bounds.min.y.min(self.source.y);
+        bounds.max.y = bounds.max.y.max(self.source.y);
+        if let Some(floor) = self.floor {
+            bounds.max.y = floor;
+        }
+
+        (bounds.min.y..=bounds.max.y)
+            .map(|y| {
+                (bounds.min.x..=bounds.max.x)
+                    .map(|x| match self.cave.get(point2!(x, y)) {
+                        Cell::Rock => '#',
+                        Cell::Sand => 'o',
+                        Cell::Air if self.floor == Some(y) => '#',
+                        Cell::Air if point2!(x, y) == self.source => '+',
+                        Cell::Air => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Same format as the examples of the puzzle
+    fn to_ascii(&self) -> String {
+        self.rows()
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
     }
-    if !map.is_occupied(point + Point::unit_y() + Point::unit_x()) {
-        return point + Point::unit_y() + Point::unit_x();
+
+    fn to_image(&self) -> Image {
+        Image::from_grid(&self.rows(), |c| match c {
+            '#' => Color(96, 96, 96),
+            'o' => Color(224, 192, 96),
+            '+' => Color(224, 32, 32),
+            _ => Color::WHITE,
+        })
     }
-    point
 }
 
-fn pour_sand(map: &mut Map, has_floor: bool) -> bool {
-    let mut coord = Point { x: 500, y: 0 };
+impl Simulation for Sand {
+    type Observation = Option<Point>;
 
-    loop {
-        let fallen_coord = sand_fall(map, coord);
-        if coord == fallen_coord {
-            map.set_occupied(coord);
-            return has_floor && coord.y < 1;
+    fn step(&mut self) -> Option<Point> {
+        if self.path.is_empty() {
+            self.path.push(self.source);
         }
-        if has_floor && fallen_coord.y == map.bottom_height {
-            map.set_occupied(coord);
-            return false;
-        }
-        if !has_floor && fallen_coord.y >= map.bottom_height {
-            return true;
+
+        loop {
+            let point = *self.path.last().unwrap();
+            match self.fall(point) {
+                Some(next) if self.floor.is_none() && next.y > self.cave.lowest_rock => {
+                    // Falls into the abyss
+                    self.is_full = true;
+                    return None;
+                }
+                Some(next) => self.path.push(next),
+                None => {
+                    self.path.pop();
+                    self.cave.set(point, Cell::Sand);
+                    self.resting_grains += 1;
+                    self.is_full = point == self.source;
+                    return Some(point);
+                }
+            }
         }
-        coord = fallen_coord;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.is_full
     }
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let mut count = 0;
-    let mut map = parse_input(input);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameFormat {
+    Ascii,
+    Ppm,
+}
 
-    while !pour_sand(&mut map, false) {
-        count += 1;
+// Calls `frame` every `every` grains
+fn pour_sand(sand: Sand, every: usize, mut frame: impl FnMut(&Sand)) -> Sand {
+    let mut driver = Driver::new(sand);
+    while let Some(rested) = driver.step().copied() {
+        let sand = driver.simulation();
+        if rested.is_some() && sand.resting_grains.is_multiple_of(every) {
+            frame(sand);
+        }
     }
-    count.to_string()
+    driver.into_simulation()
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let mut count = 0;
-    let mut map = parse_input(input);
+fn export_frame(name: &str, sand: &Sand, format: FrameFormat) {
+    let file_name = format!("{}_{:06}", name, sand.resting_grains);
+    match format {
+        FrameFormat::Ascii => {
+            render::export_text(&format!("{}.txt", file_name), || sand.to_ascii())
+        }
+        FrameFormat::Ppm => render::export(&format!("{}.ppm", file_name), || sand.to_image()),
+    }
+}
 
-    while !pour_sand(&mut map, true) {
-        count += 1;
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    let sand = Sand::new(parse_input(input)?, SOURCE, false);
+    let sand = pour_sand(sand, 100, |sand| {
+        export_frame("2022_14_sand_1", sand, FrameFormat::Ascii)
+    });
+    render::export("2022_14_sand_1.ppm", || sand.to_image());
+    if is_verbose() {
+        println!("{}", sand.to_ascii());
     }
-    (count + 1).to_string()
+    Ok(sand.resting_grains.to_string())
+}
+
+pub fn puzzle_2(input: &str) -> ParseResult<String> {
+    let sand = Sand::new(parse_input(input)?, SOURCE, true);
+    let sand = pour_sand(sand, 1000, |sand| {
+        export_frame("2022_14_sand_2", sand, FrameFormat::Ppm)
+    });
+    render::export("2022_14_sand_2.ppm", || sand.to_image());
+    Ok(sand.resting_grains.to_string())
 }
 
 #[cfg(test)]
@@ -149,17 +319,77 @@ mod tests {
 
     #[test]
     fn test_can_parse_input() {
-        let parsed = parse_input(INPUT);
-        assert_eq!(parsed.data.iter().flatten().filter(|x| **x).count(), 20);
+        let parsed = parse_input(INPUT).unwrap();
+        let rocks = parsed.cells.iter().flatten().filter(|&&c| c == Cell::Rock);
+        assert_eq!(rocks.count(), 20);
+        assert_eq!(parsed.lowest_rock, 9);
+        assert_eq!(
+            parse_input("1,2 -> 3").unwrap_err().expected.as_deref(),
+            Some("rock path")
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        let sand = Sand::new(parse_input(INPUT).unwrap(), SOURCE, false);
+        let mut frames = vec![];
+        let sand = pour_sand(sand, 5, |sand| frames.push(sand.to_ascii()));
+
+        assert_eq!(frames.len(), 4);
+        assert_eq!(
+            frames[0],
+            "......+...
+..........
+..........
+..........
+....#...##
+....#...#.
+..###...#.
+......o.#.
+....oooo#.
+#########."
+        );
+        assert_eq!(
+            sand.to_ascii(),
+            "......+...
+..........
+......o...
+.....ooo..
+....#ooo##
+...o#ooo#.
+..###ooo#.
+....oooo#.
+.o.ooooo#.
+#########."
+        );
+    }
+
+    #[test]
+    fn test_growing_bounds() {
+        // Rocks and sand left of x = 0, from another source
+        let cave = parse_input("0,2 -> 2,2").unwrap();
+        let sand = pour_sand(Sand::new(cave.clone(), point2!(1, 0), false), 10, |_| ());
+        assert_eq!(sand.resting_grains, 1);
+
+        let sand = pour_sand(Sand::new(cave, point2!(1, 0), true), 10, |_| ());
+        assert_eq!(sand.resting_grains, 12);
+        assert_eq!(
+            sand.to_ascii(),
+            "...o...
+..ooo..
+.o###o.
+ooo.ooo
+#######"
+        );
     }
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(puzzle_1(INPUT), "24");
+        assert_eq!(puzzle_1(INPUT).unwrap(), "24");
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(puzzle_2(INPUT), "93");
+        assert_eq!(puzzle_2(INPUT).unwrap(), "93");
     }
 }
@@ -1,56 +1,71 @@
-use std::{collections::BTreeMap, ops::RangeInclusive};
+use std::{collections::BTreeMap, error::Error, fmt};
 
 use itertools::Itertools;
 
-use crate::structs::geometry::Point2;
+use crate::{
+    parsing::{ParseError, ParseResult},
+    structs::{
+        geometry::{point2, Diamond, Point2, Rect},
+        interval::{Interval, RangeSet},
+    },
+};
 
 type Point = Point2<i32>;
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BeaconError {
+    Parse(ParseError),
+    NoUncoveredPoint,
+    // The first two uncovered points found in the search area
+    SeveralUncoveredPoints(Point, Point),
+}
+
+impl fmt::Display for BeaconError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BeaconError::Parse(error) => write!(f, "{}", error),
+            BeaconError::NoUncoveredPoint => {
+                write!(f, "every point of the search area is covered by a sensor")
+            }
+            BeaconError::SeveralUncoveredPoints(a, b) => write!(
+                f,
+                "several points of the search area are not covered, including x={}, y={} and x={}, y={}",
+                a.x, a.y, b.x, b.y
+            ),
+        }
+    }
+}
+
+impl Error for BeaconError {}
+
+impl From<ParseError> for BeaconError {
+    fn from(error: ParseError) -> Self {
+        BeaconError::Parse(error)
+    }
+}
+
 pub(crate) struct Sensor {
     closest_beacon: Point,
     distance: i32,
 }
 
-#[derive(Clone, Copy, Eq, PartialEq)]
-enum MapState {
-    Unknown,
-    Empty,
-    Beacon,
-    Sensor,
-}
-
 mod parser {
     use std::collections::BTreeMap;
 
     use nom::{
         bytes::complete::tag,
-        character::complete::{self, line_ending},
-        multi::separated_list1,
         sequence::{preceded, separated_pair},
         IResult,
     };
 
     use super::{Point, Sensor};
-
-    fn parse_coord(input: &str) -> IResult<&str, Point> {
-        let (input, coord) = separated_pair(
-            preceded(tag("x="), complete::i32),
-            tag(", "),
-            preceded(tag("y="), complete::i32),
-        )(input)?;
-        let coord = Point {
-            x: coord.0,
-            y: coord.1,
-        };
-
-        Ok((input, coord))
-    }
+    use crate::parsing::{coordinates, parse_lines, ParseResult};
 
     fn parse_line(input: &str) -> IResult<&str, (Point, Sensor)> {
         let (input, (sensor, closest_beacon)) = separated_pair(
-            preceded(tag("Sensor at "), parse_coord),
+            preceded(tag("Sensor at "), coordinates),
             tag(": "),
-            preceded(tag("closest beacon is at "), parse_coord),
+            preceded(tag("closest beacon is at "), coordinates),
         )(input)?;
 
         let distance = sensor.distance_1(&closest_beacon);
@@ -66,129 +81,76 @@ mod parser {
         ))
     }
 
-    pub(crate) fn parse_input(input: &str) -> BTreeMap<Point, Sensor> {
-        let (_, result) = separated_list1(line_ending, parse_line)(input).unwrap();
-
-        result.into_iter().collect()
+    pub(crate) fn parse_input(input: &str) -> ParseResult<BTreeMap<Point, Sensor>> {
+        let sensors = parse_lines(input, parse_line).map_err(|e| e.expecting("sensor report"))?;
+        Ok(sensors.into_iter().collect())
     }
 }
 
-fn get_footprint(map: &BTreeMap<Point, Sensor>) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
-    if map.is_empty() {
-        return ((0..=0), (0..=0));
-    }
-
-    let x_min = map
-        .iter()
-        .map(|(sensor, closest_beacon)| sensor.x - closest_beacon.distance)
-        .min()
-        .unwrap();
-    let x_max = map
-        .iter()
-        .map(|(sensor, closest_beacon)| sensor.x + closest_beacon.distance)
-        .max()
-        .unwrap();
-    let y_min = map
-        .iter()
-        .map(|(sensor, closest_beacon)| sensor.y - closest_beacon.distance)
-        .min()
-        .unwrap();
-    let y_max = map
-        .iter()
-        .map(|(sensor, closest_beacon)| sensor.y + closest_beacon.distance)
-        .max()
-        .unwrap();
-
-    ((x_min..=x_max), (y_min..=y_max))
+fn get_row_coverage(row: i32, map: &BTreeMap<Point, Sensor>) -> RangeSet<i32> {
+    map.iter()
+        .filter_map(|(position, sensor)| {
+            let remaining = sensor.distance - (position.y - row).abs();
+            Interval::new(position.x - remaining, position.x + remaining)
+        })
+        .collect()
 }
 
-fn get_row(
-    row_number: i32,
-    map: &BTreeMap<Point, Sensor>,
-    footprint: (RangeInclusive<i32>, RangeInclusive<i32>),
-) -> Vec<MapState> {
-    let (x_range, _) = footprint;
-    let min_x = x_range.clone().min().unwrap();
-
-    let mut result = vec![MapState::Unknown; x_range.count()];
-
-    let filtered_data = map.iter().filter(|(pos_sensor, sensor)| {
-        let distance: i32 = pos_sensor.y.abs_diff(row_number).try_into().unwrap();
-        distance <= sensor.distance
-    });
-
-    for (position, sensor) in filtered_data {
-        let distance: i32 = position.y.abs_diff(row_number).try_into().unwrap();
-        let start: i32 = position.x - (sensor.distance - distance);
-        let end: i32 = position.x + (sensor.distance - distance);
-        for i in start..=end {
-            result[(i - min_x) as usize] = MapState::Empty;
-        }
-        if position.y == row_number {
-            result[(position.x - min_x) as usize] = MapState::Sensor;
-        }
-        if sensor.closest_beacon.y == row_number {
-            result[(sensor.closest_beacon.x - min_x) as usize] = MapState::Beacon;
-        }
-    }
+// Number of cells of the row which cannot contain a beacon
+fn count_covered_cells(row: i32, map: &BTreeMap<Point, Sensor>) -> usize {
+    let coverage = get_row_coverage(row, map);
+    let beacons = map
+        .values()
+        .map(|sensor| sensor.closest_beacon)
+        .filter(|beacon| beacon.y == row && coverage.contains(beacon.x))
+        .map(|beacon| beacon.x)
+        .unique()
+        .count();
+
+    coverage.len() as usize - beacons
+}
 
-    result
+fn get_coverage(map: &BTreeMap<Point, Sensor>) -> Vec<Diamond<i32>> {
+    map.iter()
+        .map(|(position, sensor)| Diamond::new(*position, sensor.distance))
+        .collect()
 }
 
-fn get_first_empty_position(subgrid_size: i32, map: &BTreeMap<Point, Sensor>) -> Option<Point> {
-    for row_number in 0..=subgrid_size {
-        let ranges = map
-            .iter()
-            .map(|(position, sensor)| {
-                let distance: i32 = position.y.abs_diff(row_number).try_into().unwrap();
-                let start: i32 = position.x - (sensor.distance - distance);
-                let end: i32 = position.x + (sensor.distance - distance);
+fn get_uncovered_points(
+    rect: &Rect<i32>,
+    map: &BTreeMap<Point, Sensor>,
+    limit: usize,
+) -> Vec<Point> {
+    Diamond::uncovered_points(&get_coverage(map), rect, limit)
+}
 
-                (start.max(0), end)
-            })
-            .sorted_by_key(|x| x.0);
-
-        let mut covered_until = -1;
-        for range in ranges {
-            let min = range.0;
-            let max = range.1;
-            if max > subgrid_size {
-                break;
-            }
+fn tuning_frequency(point: Point) -> i64 {
+    point.x as i64 * 4_000_000 + point.y as i64
+}
 
-            if min > covered_until + 1 {
-                return Some(Point {
-                    x: covered_until + 1,
-                    y: row_number,
-                });
-            }
+fn solve_1(input: &str, row_number: i32) -> ParseResult<usize> {
+    let map = parser::parse_input(input)?;
+    Ok(count_covered_cells(row_number, &map))
+}
 
-            covered_until = covered_until.max(max);
-        }
+// The distress beacon is the only uncovered point of the search area
+fn solve_2(input: &str, search_area: &Rect<i32>) -> Result<i64, BeaconError> {
+    let map = parser::parse_input(input)?;
+    // A second point is enough to tell that the beacon cannot be found
+    match get_uncovered_points(search_area, &map, 2)[..] {
+        [point] => Ok(tuning_frequency(point)),
+        [] => Err(BeaconError::NoUncoveredPoint),
+        [a, b, ..] => Err(BeaconError::SeveralUncoveredPoints(a, b)),
     }
-    None
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let row_number = 2_000_000;
-
-    let map = parser::parse_input(input);
-    let footprint = get_footprint(&map);
-    get_row(row_number, &map, footprint)
-        .iter()
-        .filter(|&&state| state == MapState::Empty)
-        .count()
-        .to_string()
+pub fn puzzle_1(input: &str) -> ParseResult<String> {
+    Ok(solve_1(input, 2_000_000)?.to_string())
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let max_coord = 4_000_000;
-
-    let map = parser::parse_input(input);
-    let first_empty_position = get_first_empty_position(max_coord, &map).unwrap();
-
-    let value = first_empty_position.x as i64 * max_coord as i64 + first_empty_position.y as i64;
-    value.to_string()
+pub fn puzzle_2(input: &str) -> Result<String, BeaconError> {
+    let search_area = Rect::new(Point::zero(), point2!(4_000_000, 4_000_000));
+    Ok(solve_2(input, &search_area)?.to_string())
 }
 
 #[cfg(test)]
@@ -210,29 +172,129 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
 
     use super::*;
 
-    #[test]
-    fn test_puzzle_1() {
-        let row_number = 10;
+    // Columns are queried as the rows of the transposed map
+    fn transpose(map: &BTreeMap<Point, Sensor>) -> BTreeMap<Point, Sensor> {
+        map.iter()
+            .map(|(position, sensor)| {
+                let beacon = sensor.closest_beacon;
+                let sensor = Sensor {
+                    closest_beacon: point2!(beacon.y, beacon.x),
+                    distance: sensor.distance,
+                };
+                (point2!(position.y, position.x), sensor)
+            })
+            .collect()
+    }
 
-        let map = parser::parse_input(INPUT);
-        let footprint = get_footprint(&map);
-        let result = get_row(row_number, &map, footprint)
-            .iter()
-            .filter(|&&state| state == MapState::Empty)
-            .count();
+    // Positions of the row inside the rectangle which are not covered by any sensor
+    fn get_row_gaps(row: i32, rect: &Rect<i32>, map: &BTreeMap<Point, Sensor>) -> RangeSet<i32> {
+        match Interval::new(rect.min.x, rect.max.x) {
+            Some(inside) if rect.min.y <= row && row <= rect.max.y => {
+                get_row_coverage(row, map).gaps(inside)
+            }
+            _ => RangeSet::new(),
+        }
+    }
 
-        assert_eq!(result, 26);
+    // Checks every cell of the rectangle
+    fn brute_force(rect: &Rect<i32>, map: &BTreeMap<Point, Sensor>) -> Vec<Point> {
+        let coverage = get_coverage(map);
+        (rect.min.x..=rect.max.x)
+            .flat_map(|x| (rect.min.y..=rect.max.y).map(move |y| point2!(x, y)))
+            .filter(|point| coverage.iter().all(|d| !d.contains(point)))
+            .collect()
     }
 
     #[test]
-    fn test_puzzle_2() {
-        let max_coord = 20;
+    fn test_line_counts() {
+        let map = parser::parse_input(INPUT).unwrap();
+        let transposed = transpose(&map);
+        let rect = Rect::new(point2!(-20, -20), point2!(40, 40));
+        let uncovered = brute_force(&rect, &map);
+        let beacons: Vec<_> = map.values().map(|s| s.closest_beacon).collect();
+        let expected = |cells: Vec<Point>| {
+            cells
+                .iter()
+                .filter(|p| !uncovered.contains(p) && !beacons.contains(p))
+                .count()
+        };
 
-        let map = parser::parse_input(INPUT);
-        let first_empty_position = get_first_empty_position(max_coord, &map).unwrap();
+        for i in -5..=25 {
+            let row = (-20..=40).map(|x| point2!(x, i)).collect();
+            let column = (-20..=40).map(|y| point2!(i, y)).collect();
+            assert_eq!(count_covered_cells(i, &map), expected(row), "row {}", i);
+            assert_eq!(
+                count_covered_cells(i, &transposed),
+                expected(column),
+                "column {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_line_gaps() {
+        let map = parser::parse_input(INPUT).unwrap();
+        let rect = Rect::new(point2!(0, 0), point2!(20, 20));
+
+        let gaps = |row, rect: &Rect<i32>, map: &BTreeMap<Point, Sensor>| -> Vec<i32> {
+            get_row_gaps(row, rect, map)
+                .intervals()
+                .iter()
+                .flat_map(|gap| gap.start()..=gap.end())
+                .collect()
+        };
+
+        assert_eq!(gaps(11, &rect, &map), vec![14]);
+        assert_eq!(gaps(14, &rect, &transpose(&map)), vec![11]);
+        assert!(get_row_gaps(10, &rect, &map).is_empty());
+        assert!(get_row_gaps(21, &rect, &map).is_empty());
+
+        let rect = Rect::new(point2!(-10, 0), point2!(30, 30));
+        let expected: Vec<_> = brute_force(&rect, &map)
+            .into_iter()
+            .filter(|p| p.y == 25)
+            .map(|p| p.x)
+            .collect();
+        assert_eq!(gaps(25, &rect, &map), expected);
+        assert_eq!(get_row_gaps(25, &rect, &map).len(), expected.len() as i32);
+    }
+
+    #[test]
+    fn test_uncovered_points() {
+        let map = parser::parse_input(INPUT).unwrap();
+        for rect in [
+            Rect::new(point2!(0, 0), point2!(20, 20)),
+            Rect::new(point2!(-8, -3), point2!(28, 26)),
+        ] {
+            assert_eq!(
+                get_uncovered_points(&rect, &map, usize::MAX),
+                brute_force(&rect, &map)
+            );
+        }
+    }
 
-        let value = first_empty_position.x as i64 * 4_000_000i64 + first_empty_position.y as i64;
+    #[test]
+    fn test_puzzle_1() {
+        assert_eq!(solve_1(INPUT, 10), Ok(26));
+    }
 
-        assert_eq!(value, 56000011);
+    #[test]
+    fn test_puzzle_2() {
+        let search_area = Rect::new(point2!(0, 0), point2!(20, 20));
+        assert_eq!(solve_2(INPUT, &search_area), Ok(56000011));
+
+        let search_area = Rect::new(point2!(0, 0), point2!(10, 10));
+        assert_eq!(
+            solve_2(INPUT, &search_area),
+            Err(BeaconError::NoUncoveredPoint)
+        );
+
+        let search_area = Rect::new(point2!(0, 0), point2!(4_000_000, 4_000_000));
+        let error = solve_2(&INPUT[..INPUT.find('\n').unwrap()], &search_area).unwrap_err();
+        assert_eq!(
+            error,
+            BeaconError::SeveralUncoveredPoints(point2!(0, 0), point2!(0, 1))
+        );
     }
 }
@@ -1,90 +1,76 @@
-use std::{
-    collections::{hash_map::DefaultHasher, BTreeSet, HashMap},
-    hash::{Hash, Hasher},
-};
+use std::{cmp::Reverse, collections::HashMap, error::Error, fmt};
 
 use self::parser::parse_input;
+use crate::{
+    algorithms::{graph::WeightedGraph, search::bfs},
+    parsing::ParseError,
+    utils::is_verbose,
+};
 
-type ValveName = (char, char);
-
-pub(crate) struct InputData {
-    paths: HashMap<ValveName, (Vec<ValveName>, u16)>,
-    flow_rate: Vec<(ValveName, u16)>,
-}
+type ValveId = usize;
 
-#[derive(Hash, Clone)]
-struct ValvesConfiguration {
-    data: [[bool; 26]; 26],
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ValveError {
+    Parse(ParseError),
+    MissingStartValve(String),
+    // Number of valves with a positive flow rate that can be reached from the start
+    TooManyValves(usize),
 }
 
-impl ValvesConfiguration {
-    fn new() -> Self {
-        Self {
-            data: [[false; 26]; 26],
+impl fmt::Display for ValveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValveError::Parse(error) => write!(f, "{}", error),
+            ValveError::MissingStartValve(name) => write!(f, "missing start valve `{}`", name),
+            ValveError::TooManyValves(count) => write!(
+                f,
+                "{} valves with a positive flow rate can be reached, at most 64 are supported",
+                count
+            ),
         }
     }
+}
 
-    fn is_open(&self, valve_name: ValveName) -> bool {
-        let a_val = 'A' as usize;
-
-        let id_1 = valve_name.0 as usize - a_val;
-        let id_2 = valve_name.1 as usize - a_val;
-
-        self.data[id_1][id_2]
-    }
-
-    fn open(&mut self, valve_name: &ValveName) {
-        let a_val = 'A' as usize;
-
-        let id_1 = valve_name.0 as usize - a_val;
-        let id_2 = valve_name.1 as usize - a_val;
+impl Error for ValveError {}
 
-        self.data[id_1][id_2] = true;
+impl From<ParseError> for ValveError {
+    fn from(error: ParseError) -> Self {
+        ValveError::Parse(error)
     }
 }
 
-struct Node {
-    human_valve: ValveName,
-    elephant_valve: ValveName,
-    released_pressure: u16,
-    minute: u8,
-    configuration: ValvesConfiguration,
-}
+const START_VALVE: &str = "AA";
 
-struct NodeContainer {
-    nodes: Vec<Node>,
-    visited_nodes: BTreeSet<u64>,
+// Valves are referred to by their index in the input
+#[derive(Debug)]
+pub(crate) struct InputData {
+    names: Vec<String>,
+    ids: HashMap<String, ValveId>,
+    paths: Vec<Vec<ValveId>>,
+    flow_rates: Vec<u16>,
 }
 
-impl NodeContainer {
-    fn new() -> Self {
-        Self {
-            nodes: Vec::new(),
-            visited_nodes: BTreeSet::new(),
-        }
+impl InputData {
+    fn id(&self, name: &str) -> Option<ValveId> {
+        self.ids.get(name).copied()
     }
 
-    fn insert(&mut self, node: Node) {
-        let mut hasher = DefaultHasher::new();
-        node.hash(&mut hasher);
-        if self.visited_nodes.insert(hasher.finish()) {
-            self.nodes.push(node);
-        }
-    }
-
-    fn pop(&mut self) -> Option<Node> {
-        // dbg!(self.nodes.len());
-        self.nodes.pop()
+    fn name(&self, id: ValveId) -> &str {
+        &self.names[id]
     }
 }
 
-impl Hash for Node {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.human_valve.hash(state);
-        self.elephant_valve.hash(state);
-        self.released_pressure.hash(state);
-        self.minute.hash(state);
-        // self.configuration.hash(state);
+// Each tunnel takes one minute to go through
+impl From<&InputData> for WeightedGraph<ValveId, u16> {
+    fn from(input_data: &InputData) -> Self {
+        let mut graph = WeightedGraph::new();
+        for (valve, paths) in input_data.paths.iter().enumerate() {
+            graph.add_node(valve);
+            for path in paths {
+                graph.add_edge(valve, *path, 1);
+            }
+        }
+        graph
     }
 }
 
@@ -93,31 +79,27 @@ mod parser {
     // A line will look like that:
     //   Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
 
+    use std::collections::HashMap;
+
     use nom::{
         branch::alt,
         bytes::complete::tag,
-        character::complete::{self, newline},
-        character::streaming::anychar,
+        character::complete::{self, alphanumeric1},
         multi::separated_list1,
-        sequence::{pair, preceded, separated_pair, tuple},
+        sequence::{preceded, separated_pair, tuple},
         IResult,
     };
 
-    use super::{InputData, ValveName};
+    use super::InputData;
+    use crate::parsing::{self, column_of, ParseError, ParseResult};
 
-    type Valve = (u16, Vec<ValveName>);
+    type Valve<'a> = (&'a str, u16, Vec<&'a str>);
 
-    fn parse_valve_name(input: &str) -> IResult<&str, ValveName> {
-        let (input, valve_name) = pair(anychar, anychar)(input)?;
-
-        Ok((input, valve_name))
-    }
-
-    fn parse_line(input: &str) -> IResult<&str, (ValveName, Valve)> {
+    fn parse_line(input: &str) -> IResult<&str, Valve<'_>> {
         let (input, ((_, name, _, flow_rate), paths)) = separated_pair(
             tuple((
                 tag("Valve "),
-                parse_valve_name,
+                alphanumeric1,
                 tag(" has flow rate="),
                 complete::u16,
             )),
@@ -127,220 +109,652 @@ mod parser {
                     tag(" tunnels lead to valves "),
                     tag(" tunnel leads to valve "),
                 )),
-                separated_list1(tag(", "), parse_valve_name),
+                separated_list1(tag(", "), alphanumeric1),
             ),
         )(input)?;
 
-        let valve = (flow_rate, paths);
-        Ok((input, (name, valve)))
+        Ok((input, (name, flow_rate, paths)))
     }
 
-    pub(crate) fn parse_input(input: &str) -> InputData {
-        let (_, parsed) = separated_list1(newline, parse_line)(input).unwrap();
-
-        let flow_rate = parsed
-            .clone()
-            .into_iter()
-            .map(|(name, (flow_rate, _))| (name, flow_rate))
-            .collect();
+    pub(crate) fn parse_input(input: &str) -> ParseResult<InputData> {
+        let valves = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                parsing::parse_line(i + 1, line, parse_line)
+                    .map(|valve| (i + 1, line, valve))
+                    .map_err(|e| e.expecting("valve description"))
+            })
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        let mut ids = HashMap::new();
+        for (line_number, line, (name, _, _)) in &valves {
+            if ids.insert(*name, ids.len()).is_some() {
+                return Err(ParseError::at(
+                    *line_number,
+                    line,
+                    column_of(line, name),
+                    format!("valve `{}` is already defined", name),
+                ));
+            }
+        }
 
-        let paths = parsed
-            .into_iter()
-            .map(|(name, (flow_rate, paths))| (name, (paths, flow_rate)))
-            .collect();
+        let mut data = InputData {
+            names: vec![],
+            ids: ids
+                .iter()
+                .map(|(name, id)| (name.to_string(), *id))
+                .collect(),
+            paths: vec![],
+            flow_rates: vec![],
+        };
+        for (line_number, line, (name, flow_rate, paths)) in valves {
+            let paths = paths
+                .into_iter()
+                .map(|path| {
+                    ids.get(path).copied().ok_or_else(|| {
+                        ParseError::at(
+                            line_number,
+                            line,
+                            column_of(line, path),
+                            format!("unknown valve `{}`", path),
+                        )
+                    })
+                })
+                .collect::<ParseResult<_>>()?;
+            data.names.push(name.to_string());
+            data.paths.push(paths);
+            data.flow_rates.push(flow_rate);
+        }
 
-        InputData { paths, flow_rate }
+        Ok(data)
     }
 }
 
-fn get_released_pressure(node: &Node, input_data: &InputData) -> u16 {
-    input_data.flow_rate.iter().fold(0, |acc, (key, val)| {
-        match node.configuration.is_open(*key) {
-            true => acc + val,
-            false => acc,
+// Exhaustive search over the joint moves, kept to cross-check the bitmask solver
+#[cfg(test)]
+mod brute_force {
+    use std::{
+        collections::{hash_map::DefaultHasher, BTreeSet},
+        hash::{Hash, Hasher},
+    };
+
+    use super::{InputData, ValveId};
+
+    #[derive(Hash, Clone)]
+    struct ValvesConfiguration {
+        data: Vec<bool>,
+    }
+
+    impl ValvesConfiguration {
+        fn new(size: usize) -> Self {
+            Self {
+                data: vec![false; size],
+            }
         }
-    })
-}
 
-fn get_starting_node() -> Node {
-    let configuration = ValvesConfiguration::new();
+        fn is_open(&self, valve: ValveId) -> bool {
+            self.data[valve]
+        }
 
-    let initial_valve = ('A', 'A');
+        fn open(&mut self, valve: &ValveId) {
+            self.data[*valve] = true;
+        }
+    }
 
-    Node {
-        human_valve: initial_valve,
-        elephant_valve: initial_valve,
-        released_pressure: 0,
-        minute: 0,
-        configuration,
+    struct Node {
+        human_valve: ValveId,
+        elephant_valve: ValveId,
+        released_pressure: u32,
+        minute: u8,
+        configuration: ValvesConfiguration,
     }
-}
 
-fn human_opens(
-    node: &Node,
-    nodes: &mut NodeContainer,
-    input_data: &InputData,
-    use_elephants: bool,
-) {
-    let path_elephants: Box<dyn Iterator<Item = &ValveName>> = match use_elephants {
-        true => Box::new(input_data.paths.get(&node.elephant_valve).unwrap().0.iter()),
-        false => Box::new(std::iter::once(&node.elephant_valve)),
-    };
+    struct NodeContainer {
+        nodes: Vec<Node>,
+        visited_nodes: BTreeSet<u64>,
+    }
 
-    path_elephants
-        .filter(|path| {
-            !(node.configuration.is_open(node.human_valve) && node.configuration.is_open(**path))
-        })
-        .for_each(|path| {
-            let mut new_configuration = node.configuration.clone();
-            new_configuration.open(&node.human_valve);
-
-            let released_pressure = get_released_pressure(node, input_data);
-
-            let new_node = Node {
-                human_valve: node.human_valve,
-                elephant_valve: *path,
-                released_pressure: node.released_pressure + released_pressure,
-                minute: node.minute + 1,
-                configuration: new_configuration,
-            };
+    impl NodeContainer {
+        fn new() -> Self {
+            Self {
+                nodes: Vec::new(),
+                visited_nodes: BTreeSet::new(),
+            }
+        }
 
-            nodes.insert(new_node);
-        });
-}
+        fn insert(&mut self, node: Node) {
+            let mut hasher = DefaultHasher::new();
+            node.hash(&mut hasher);
+            if self.visited_nodes.insert(hasher.finish()) {
+                self.nodes.push(node);
+            }
+        }
 
-fn elephant_opens(
-    node: &Node,
-    nodes: &mut NodeContainer,
-    input_data: &InputData,
-    _use_elephants: bool,
-) {
-    input_data
-        .paths
-        .get(&node.human_valve)
-        .unwrap()
-        .0
-        .iter()
-        .filter(|path| {
-            !(node.configuration.is_open(node.elephant_valve) && node.configuration.is_open(**path))
-        })
-        .for_each(|path| {
-            let mut new_configuration = node.configuration.clone();
-            new_configuration.open(&node.elephant_valve);
+        fn pop(&mut self) -> Option<Node> {
+            self.nodes.pop()
+        }
+    }
 
-            let released_pressure = get_released_pressure(node, input_data);
+    impl Hash for Node {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.human_valve.hash(state);
+            self.elephant_valve.hash(state);
+            self.released_pressure.hash(state);
+            self.minute.hash(state);
+        }
+    }
 
-            let new_node = Node {
-                human_valve: *path,
-                elephant_valve: node.elephant_valve,
-                released_pressure: node.released_pressure + released_pressure,
-                minute: node.minute + 1,
-                configuration: new_configuration,
-            };
+    fn get_released_pressure(node: &Node, input_data: &InputData) -> u32 {
+        input_data
+            .flow_rates
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (valve, val)| {
+                match node.configuration.is_open(valve) {
+                    true => acc + u32::from(*val),
+                    false => acc,
+                }
+            })
+    }
 
-            nodes.insert(new_node);
-        });
-}
+    fn get_starting_node(input_data: &InputData, initial_valve: ValveId) -> Node {
+        let configuration = ValvesConfiguration::new(input_data.flow_rates.len());
 
-fn both_opens(node: &Node, nodes: &mut NodeContainer, input_data: &InputData, use_elephants: bool) {
-    let mut new_configuration = node.configuration.clone();
-    if use_elephants {
-        new_configuration.open(&node.elephant_valve);
+        Node {
+            human_valve: initial_valve,
+            elephant_valve: initial_valve,
+            released_pressure: 0,
+            minute: 0,
+            configuration,
+        }
     }
-    new_configuration.open(&node.human_valve);
 
-    let released_pressure = get_released_pressure(node, input_data);
+    fn human_opens(
+        node: &Node,
+        nodes: &mut NodeContainer,
+        input_data: &InputData,
+        use_elephants: bool,
+    ) {
+        let path_elephants: Box<dyn Iterator<Item = &ValveId>> = match use_elephants {
+            true => Box::new(input_data.paths[node.elephant_valve].iter()),
+            false => Box::new(std::iter::once(&node.elephant_valve)),
+        };
 
-    let new_node = Node {
-        human_valve: node.human_valve,
-        elephant_valve: node.elephant_valve,
-        released_pressure: node.released_pressure + released_pressure,
-        minute: node.minute + 1,
-        configuration: new_configuration,
-    };
+        path_elephants
+            .filter(|path| {
+                !(node.configuration.is_open(node.human_valve)
+                    && node.configuration.is_open(**path))
+            })
+            .for_each(|path| {
+                let mut new_configuration = node.configuration.clone();
+                new_configuration.open(&node.human_valve);
+
+                let released_pressure = get_released_pressure(node, input_data);
+
+                let new_node = Node {
+                    human_valve: node.human_valve,
+                    elephant_valve: *path,
+                    released_pressure: node.released_pressure + released_pressure,
+                    minute: node.minute + 1,
+                    configuration: new_configuration,
+                };
+
+                nodes.insert(new_node);
+            });
+    }
 
-    nodes.insert(new_node);
-}
+    fn elephant_opens(
+        node: &Node,
+        nodes: &mut NodeContainer,
+        input_data: &InputData,
+        _use_elephants: bool,
+    ) {
+        input_data.paths[node.human_valve]
+            .iter()
+            .filter(|path| {
+                !(node.configuration.is_open(node.elephant_valve)
+                    && node.configuration.is_open(**path))
+            })
+            .for_each(|path| {
+                let mut new_configuration = node.configuration.clone();
+                new_configuration.open(&node.elephant_valve);
+
+                let released_pressure = get_released_pressure(node, input_data);
+
+                let new_node = Node {
+                    human_valve: *path,
+                    elephant_valve: node.elephant_valve,
+                    released_pressure: node.released_pressure + released_pressure,
+                    minute: node.minute + 1,
+                    configuration: new_configuration,
+                };
+
+                nodes.insert(new_node);
+            });
+    }
 
-fn both_moves(node: &Node, nodes: &mut NodeContainer, input_data: &InputData, use_elephants: bool) {
-    let path_elephants: Box<dyn Iterator<Item = &ValveName>> = match use_elephants {
-        true => Box::new(input_data.paths.get(&node.elephant_valve).unwrap().0.iter()),
-        false => Box::new(std::iter::once(&node.elephant_valve)),
-    };
+    fn both_opens(
+        node: &Node,
+        nodes: &mut NodeContainer,
+        input_data: &InputData,
+        use_elephants: bool,
+    ) {
+        let mut new_configuration = node.configuration.clone();
+        if use_elephants {
+            new_configuration.open(&node.elephant_valve);
+        }
+        new_configuration.open(&node.human_valve);
 
-    path_elephants.for_each(|path_elephant| {
-        let path_humans = input_data
-            .paths
-            .get(&node.human_valve)
-            .unwrap()
-            .0
-            .iter()
-            .filter(|path_human| {
-                !(node.configuration.is_open(*path_elephant)
-                    && node.configuration.is_open(**path_human))
+        let released_pressure = get_released_pressure(node, input_data);
+
+        let new_node = Node {
+            human_valve: node.human_valve,
+            elephant_valve: node.elephant_valve,
+            released_pressure: node.released_pressure + released_pressure,
+            minute: node.minute + 1,
+            configuration: new_configuration,
+        };
+
+        nodes.insert(new_node);
+    }
+
+    fn both_moves(
+        node: &Node,
+        nodes: &mut NodeContainer,
+        input_data: &InputData,
+        use_elephants: bool,
+    ) {
+        let path_elephants: Box<dyn Iterator<Item = &ValveId>> = match use_elephants {
+            true => Box::new(input_data.paths[node.elephant_valve].iter()),
+            false => Box::new(std::iter::once(&node.elephant_valve)),
+        };
+
+        path_elephants.for_each(|path_elephant| {
+            let path_humans = input_data.paths[node.human_valve]
+                .iter()
+                .filter(|path_human| {
+                    !(node.configuration.is_open(*path_elephant)
+                        && node.configuration.is_open(**path_human))
+                });
+            path_humans.for_each(|path_human| {
+                let released_pressure = get_released_pressure(node, input_data);
+
+                let new_node = Node {
+                    human_valve: *path_human,
+                    elephant_valve: *path_elephant,
+                    released_pressure: node.released_pressure + released_pressure,
+                    minute: node.minute + 1,
+                    configuration: node.configuration.clone(),
+                };
+
+                nodes.insert(new_node);
             });
-        path_humans.for_each(|path_human| {
-            let released_pressure = get_released_pressure(node, input_data);
-
-            let new_node = Node {
-                human_valve: *path_human,
-                elephant_valve: *path_elephant,
-                released_pressure: node.released_pressure + released_pressure,
-                minute: node.minute + 1,
-                configuration: node.configuration.clone(),
+        });
+    }
+
+    pub(super) fn solve(
+        input_data: &InputData,
+        start: ValveId,
+        max_minute: u8,
+        use_elephants: bool,
+    ) -> u32 {
+        let mut nodes = NodeContainer::new();
+
+        nodes.insert(get_starting_node(input_data, start));
+
+        let mut max_flow_rate = 0;
+
+        while let Some(node) = nodes.pop() {
+            max_flow_rate = max_flow_rate.max(node.released_pressure);
+
+            if node.minute == max_minute {
+                continue;
             };
 
-            nodes.insert(new_node);
-        });
-    });
+            let can_human_open = !node.configuration.is_open(node.human_valve)
+                && input_data.flow_rates[node.human_valve] > 0;
+            let can_elephant_open = !node.configuration.is_open(node.elephant_valve)
+                && input_data.flow_rates[node.elephant_valve] > 0;
+
+            if use_elephants && can_elephant_open && can_human_open {
+                both_opens(&node, &mut nodes, input_data, use_elephants);
+            }
+            if can_human_open {
+                human_opens(&node, &mut nodes, input_data, use_elephants);
+            }
+            if use_elephants && can_elephant_open {
+                elephant_opens(&node, &mut nodes, input_data, use_elephants);
+            }
+            both_moves(&node, &mut nodes, input_data, use_elephants);
+        }
+
+        max_flow_rate
+    }
 }
 
-fn solve(input_data: &InputData, max_minute: u8, use_elephants: bool) -> u16 {
-    let mut nodes = NodeContainer::new();
+// Valves with a positive flow rate, the set of open valves being a bitmask over their indices
+struct ValveNetwork {
+    // Id in the input of each valve, followed by the start valve
+    valves: Vec<ValveId>,
+    flow_rates: Vec<u32>,
+    // Travel time between valves, None when there is no path. The last row is the one of
+    // the start valve.
+    distances: Vec<Vec<Option<u16>>>,
+}
+
+impl ValveNetwork {
+    fn new(input_data: &InputData, start: ValveId) -> Result<Self, ValveError> {
+        let is_useful = |valve: &ValveId| input_data.flow_rates[*valve] > 0;
+        let graph =
+            WeightedGraph::from(input_data).compress(|valve| *valve == start || is_useful(valve));
+        let matrix = graph.floyd_warshall();
+        let distance =
+            |from: &ValveId, to: &ValveId| matrix.get(graph.index_of(from)?, graph.index_of(to)?);
+
+        // Valves that cannot be reached from the start are never opened
+        let mut valves: Vec<_> = graph
+            .nodes()
+            .iter()
+            .copied()
+            .filter(|valve| is_useful(valve) && distance(&start, valve).is_some())
+            .collect();
+        if valves.len() > 64 {
+            return Err(ValveError::TooManyValves(valves.len()));
+        }
+        let flow_rates = valves
+            .iter()
+            .map(|valve| input_data.flow_rates[*valve].into())
+            .collect();
 
-    nodes.insert(get_starting_node());
+        valves.push(start);
+        let distances = valves
+            .iter()
+            .map(|from| {
+                valves[..valves.len() - 1]
+                    .iter()
+                    .map(|to| distance(from, to))
+                    .collect()
+            })
+            .collect();
 
-    let mut max_flow_rate = 0;
+        Ok(Self {
+            valves,
+            flow_rates,
+            distances,
+        })
+    }
 
-    while let Some(node) = nodes.pop() {
-        max_flow_rate = max_flow_rate.max(node.released_pressure);
+    fn start(&self) -> usize {
+        self.flow_rates.len()
+    }
 
-        if node.minute == max_minute {
-            continue;
-        };
+    // Best pressure released for each set of valves that can be opened within the time limit
+    fn best_by_subset(&self, minutes: u16) -> HashMap<u64, u32> {
+        let mut best = HashMap::new();
+        let mut stack = vec![(self.start(), minutes, 0u64, 0u32)];
+
+        while let Some((position, time_left, open, pressure)) = stack.pop() {
+            let entry = best.entry(open).or_insert(0);
+            *entry = pressure.max(*entry);
+
+            for (valve, flow_rate) in self.flow_rates.iter().enumerate() {
+                let Some(distance) = self.distances[position][valve] else {
+                    continue;
+                };
+                let travel = distance + 1;
+                if open & (1 << valve) != 0 || travel >= time_left {
+                    continue;
+                }
+                let remaining = time_left - travel;
+                stack.push((
+                    valve,
+                    remaining,
+                    open | 1 << valve,
+                    pressure + flow_rate * u32::from(remaining),
+                ));
+            }
+        }
 
-        let can_human_open = !node.configuration.is_open(node.human_valve)
-            && input_data.paths.get(&node.human_valve).unwrap().1 > 0;
-        let can_elephant_open = !node.configuration.is_open(node.elephant_valve)
-            && input_data.paths.get(&node.elephant_valve).unwrap().1 > 0;
+        best
+    }
 
-        if use_elephants && can_elephant_open && can_human_open {
-            both_opens(&node, &mut nodes, input_data, use_elephants);
+    fn solve(&self, minutes: u16) -> u32 {
+        self.best_by_subset(minutes)
+            .into_values()
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Both actors open disjoint sets of valves, returns the best pressure and the two sets
+    fn best_split(&self, minutes: u16) -> (u32, u64, u64) {
+        let mut subsets: Vec<_> = self.best_by_subset(minutes).into_iter().collect();
+        subsets.sort_unstable_by_key(|(_, pressure)| Reverse(*pressure));
+
+        let mut best = (0, 0, 0);
+        for (i, (human, human_pressure)) in subsets.iter().enumerate() {
+            if 2 * human_pressure <= best.0 {
+                break;
+            }
+            for (elephant, elephant_pressure) in &subsets[i..] {
+                if human_pressure + elephant_pressure <= best.0 {
+                    break;
+                }
+                if human & elephant == 0 {
+                    best = (human_pressure + elephant_pressure, *human, *elephant);
+                }
+            }
         }
-        if can_human_open {
-            human_opens(&node, &mut nodes, input_data, use_elephants);
+        best
+    }
+
+    fn solve_with_elephant(&self, minutes: u16) -> u32 {
+        self.best_split(minutes).0
+    }
+
+    // Best order to open the valves of the set, as (valve, minute at which it is opened)
+    fn best_order(&self, minutes: u16, allowed: u64) -> Vec<(usize, u16)> {
+        fn explore(
+            network: &ValveNetwork,
+            position: usize,
+            time_left: u16,
+            remaining: u64,
+            pressure: u32,
+            order: &mut Vec<(usize, u16)>,
+            best: &mut (u32, Vec<(usize, u16)>),
+        ) {
+            if pressure > best.0 || best.1.is_empty() {
+                *best = (pressure, order.clone());
+            }
+            for valve in (0..network.flow_rates.len()).filter(|v| remaining & (1 << v) != 0) {
+                let Some(distance) = network.distances[position][valve] else {
+                    continue;
+                };
+                let travel = distance + 1;
+                if travel >= time_left {
+                    continue;
+                }
+                let time_left = time_left - travel;
+                order.push((valve, time_left));
+                explore(
+                    network,
+                    valve,
+                    time_left,
+                    remaining & !(1 << valve),
+                    pressure + network.flow_rates[valve] * u32::from(time_left),
+                    order,
+                    best,
+                );
+                order.pop();
+            }
         }
-        if use_elephants && can_elephant_open {
-            elephant_opens(&node, &mut nodes, input_data, use_elephants);
+
+        let mut best = (0, vec![]);
+        explore(
+            self,
+            self.start(),
+            minutes,
+            allowed,
+            0,
+            &mut vec![],
+            &mut best,
+        );
+        best.1
+            .into_iter()
+            .map(|(valve, time_left)| (valve, minutes - time_left))
+            .collect()
+    }
+
+    fn schedule(&self, input_data: &InputData, minutes: u16, actors: usize) -> Schedule {
+        let subsets = match actors {
+            1 => {
+                let best = self.best_by_subset(minutes);
+                let subset = best.iter().max_by_key(|(_, p)| **p).map_or(0, |(s, _)| *s);
+                vec![subset]
+            }
+            _ => {
+                let (_, human, elephant) = self.best_split(minutes);
+                vec![human, elephant]
+            }
+        };
+
+        let tunnels = WeightedGraph::<ValveId, u16>::from(input_data);
+        let actions = subsets
+            .into_iter()
+            .map(|subset| {
+                let mut actions = vec![];
+                let mut position = self.valves[self.start()];
+                for (valve, minute) in self.best_order(minutes, subset) {
+                    let valve = self.valves[valve];
+                    let path = bfs(&tunnels, [position], |v| *v == valve)
+                        .expect("Unreachable valve")
+                        .path();
+                    actions.extend(path[1..].iter().map(|v| Action::Move(*v)));
+                    actions.push(Action::Open(valve));
+                    debug_assert_eq!(actions.len(), minute as usize);
+                    position = valve;
+                }
+                actions
+            })
+            .collect();
+
+        Schedule { minutes, actions }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Action {
+    Move(ValveId),
+    Open(ValveId),
+}
+
+// What each actor does minute by minute, they stay idle after their last action
+struct Schedule {
+    minutes: u16,
+    actions: Vec<Vec<Action>>,
+}
+
+impl Schedule {
+    // Pressure released during each minute
+    fn pressure_by_minute(&self, input_data: &InputData) -> Vec<u32> {
+        let mut released = vec![0; self.minutes as usize];
+        for actions in &self.actions {
+            for (minute, action) in actions.iter().enumerate() {
+                if let Action::Open(valve) = action {
+                    released[minute + 1..]
+                        .iter_mut()
+                        .for_each(|r| *r += u32::from(input_data.flow_rates[*valve]));
+                }
+            }
         }
-        both_moves(&node, &mut nodes, input_data, use_elephants);
+        released
     }
 
-    max_flow_rate
+    // Same format as the walkthrough of the puzzle
+    fn describe(&self, input_data: &InputData) -> String {
+        let actors = [("You", "move", "open"), ("The elephant", "moves", "opens")];
+        let pressures = self.pressure_by_minute(input_data);
+        let mut open: Vec<&str> = vec![];
+        let mut total = 0;
+        let mut lines = vec![];
+
+        for (minute, pressure) in pressures.into_iter().enumerate() {
+            lines.push(format!("== Minute {} ==", minute + 1));
+            open.sort_unstable();
+            lines.push(match open.as_slice() {
+                [] => "No valves are open.".to_string(),
+                [valve] => format!("Valve {} is open, releasing {} pressure.", valve, pressure),
+                [first @ .., last] => format!(
+                    "Valves {}{} and {} are open, releasing {} pressure.",
+                    first.join(", "),
+                    if first.len() > 1 { "," } else { "" },
+                    last,
+                    pressure
+                ),
+            });
+            total += pressure;
+
+            for (actions, (actor, move_verb, open_verb)) in self.actions.iter().zip(actors) {
+                match actions.get(minute) {
+                    Some(Action::Move(valve)) => lines.push(format!(
+                        "{} {} to valve {}.",
+                        actor,
+                        move_verb,
+                        input_data.name(*valve)
+                    )),
+                    Some(Action::Open(valve)) => {
+                        lines.push(format!(
+                            "{} {} valve {}.",
+                            actor,
+                            open_verb,
+                            input_data.name(*valve)
+                        ));
+                        open.push(input_data.name(*valve));
+                    }
+                    None => (),
+                }
+            }
+            lines.push(format!("Released so far: {}", total));
+            lines.push(String::new());
+        }
+
+        lines.join("\n")
+    }
 }
 
-pub fn puzzle_1(input: &str) -> String {
-    let input_data = parse_input(input);
+fn get_network(input_data: &InputData, start: &str) -> Result<ValveNetwork, ValveError> {
+    let start = input_data
+        .id(start)
+        .ok_or_else(|| ValveError::MissingStartValve(start.to_string()))?;
+    ValveNetwork::new(input_data, start)
+}
 
-    solve(&input_data, 30, false).to_string()
+fn print_schedule(input_data: &InputData, network: &ValveNetwork, minutes: u16, actors: usize) {
+    if is_verbose() {
+        println!(
+            "{}",
+            network
+                .schedule(input_data, minutes, actors)
+                .describe(input_data)
+        );
+    }
 }
 
-pub fn puzzle_2(input: &str) -> String {
-    let input_data = parse_input(input);
+pub fn puzzle_1(input: &str) -> Result<String, ValveError> {
+    let input_data = parse_input(input)?;
+    let network = get_network(&input_data, START_VALVE)?;
+    print_schedule(&input_data, &network, 30, 1);
 
-    // This bruteforce solution takes a long time, TODO: find a more clever solution
+    Ok(network.solve(30).to_string())
+}
+
+pub fn puzzle_2(input: &str) -> Result<String, ValveError> {
+    let input_data = parse_input(input)?;
+    let network = get_network(&input_data, START_VALVE)?;
+    print_schedule(&input_data, &network, 26, 2);
 
-    solve(&input_data, 26, true).to_string()
+    Ok(network.solve_with_elephant(26).to_string())
 }
 
 #[cfg(test)]
@@ -360,18 +774,193 @@ Valve JJ has flow rate=21; tunnel leads to valve II";
 
     #[test]
     fn test_parse_input() {
-        let parsed = parse_input(INPUT);
-        let paths = parsed.paths.get(&('A', 'A')).unwrap();
-        assert_eq!(paths, &(vec![('D', 'D'), ('I', 'I'), ('B', 'B')], 0),);
+        let parsed = parse_input(INPUT).unwrap();
+        let start = parsed.id("AA").unwrap();
+        let paths: Vec<_> = parsed.paths[start]
+            .iter()
+            .map(|&v| parsed.names[v].as_str())
+            .collect();
+        assert_eq!(paths, vec!["DD", "II", "BB"]);
+        assert_eq!(parsed.flow_rates[start], 0);
+    }
+
+    #[test]
+    fn test_parse_any_name() {
+        let input = "Valve start has flow rate=0; tunnels lead to valves b2, CCC
+Valve b2 has flow rate=5; tunnel leads to valve start
+Valve CCC has flow rate=7; tunnel leads to valve start";
+        let parsed = parse_input(input).unwrap();
+        assert_eq!(parsed.id("CCC"), Some(2));
+
+        let network = ValveNetwork::new(&parsed, parsed.id("start").unwrap()).unwrap();
+        // Opens CCC at minute 2 then b2 at minute 5
+        assert_eq!(network.solve(6), 7 * 4 + 5);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let unknown = INPUT.replace("valves FF, DD", "valves FF, ZZ");
+        let error = parse_input(&unknown).unwrap_err();
+        assert_eq!((error.line, error.column), (5, Some(54)));
+        assert_eq!(error.message, "unknown valve `ZZ`");
+        assert_eq!(puzzle_1(&unknown), Err(ValveError::Parse(error)));
+
+        let invalid = INPUT.replace("rate=3", "rate=?");
+        let error = parse_input(&invalid).unwrap_err();
+        assert_eq!((error.line, error.column), (5, Some(24)));
+        assert_eq!(error.expected.as_deref(), Some("valve description"));
+    }
+
+    #[test]
+    fn test_unreachable_valves() {
+        // BB is a dead end, and CC cannot be reached at all
+        let input = "Valve AA has flow rate=0; tunnel leads to valve BB
+Valve BB has flow rate=5; tunnel leads to valve BB
+Valve CC has flow rate=9; tunnel leads to valve AA";
+        let input_data = parse_input(input).unwrap();
+        let network = get_network(&input_data, START_VALVE).unwrap();
+        assert_eq!(network.valves, vec![1, 0]);
+        assert_eq!(network.solve(30), 5 * 28);
+        assert_eq!(network.solve_with_elephant(26), 5 * 24);
+
+        let error = get_network(&input_data, "ZZ").err().unwrap();
+        assert_eq!(error, ValveError::MissingStartValve("ZZ".to_string()));
+        assert_eq!(error.to_string(), "missing start valve `ZZ`");
+    }
+
+    #[test]
+    fn test_large_networks() {
+        let valves = |count: usize, flow_rate: u16| {
+            let names: Vec<_> = (0..count).map(|i| format!("V{}", i)).collect();
+            let tunnels = names.join(", ");
+            let mut lines = vec![format!(
+                "Valve AA has flow rate=0; tunnels lead to valves {}",
+                tunnels
+            )];
+            lines.extend(names.iter().map(|name| {
+                format!(
+                    "Valve {} has flow rate={}; tunnel leads to valve AA",
+                    name, flow_rate
+                )
+            }));
+            lines.join("\n")
+        };
+
+        let error = puzzle_1(&valves(65, 1)).unwrap_err();
+        assert_eq!(error, ValveError::TooManyValves(65));
+
+        // The released pressure does not fit in 16 bits
+        let input_data = parse_input(&valves(5, 60000)).unwrap();
+        let network = get_network(&input_data, START_VALVE).unwrap();
+        assert_eq!(network.solve(30), 60000 * (28 + 25 + 22 + 19 + 16));
+    }
+
+    #[test]
+    fn test_compressed_graph() {
+        use crate::algorithms::search::Graph;
+
+        let parsed = parse_input(INPUT).unwrap();
+        let graph = WeightedGraph::from(&parsed)
+            .compress(|&valve| valve == 0 || parsed.flow_rates[valve] > 0);
+        let id = |name| parsed.id(name).unwrap();
+
+        assert_eq!(graph.len(), 7);
+        assert_eq!(graph.cost(&id("AA"), &id("HH")), 5);
+        assert_eq!(graph.cost(&id("JJ"), &id("BB")), 3);
+    }
+
+    #[test]
+    fn test_distances_against_bfs() {
+        use crate::algorithms::graph::all_pairs_bfs;
+
+        let parsed = parse_input(INPUT).unwrap();
+        let graph = WeightedGraph::from(&parsed);
+        let matrix = graph.floyd_warshall();
+        let steps = all_pairs_bfs(&graph, graph.nodes().iter().copied());
+
+        assert!(!graph.is_empty());
+        assert_eq!(matrix.size(), graph.len());
+        for from in 0..graph.len() {
+            assert_eq!(
+                graph.edges(from).len(),
+                parsed.paths[*graph.node(from)].len()
+            );
+            for to in 0..graph.len() {
+                let bfs_distance = steps.get(&(*graph.node(from), *graph.node(to)));
+                assert_eq!(matrix.get(from, to), bfs_distance.map(|&d| d as u16));
+            }
+        }
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        // Same tunnels with different flow rates, so that another order is optimal
+        let variant = INPUT
+            .replace("rate=13", "rate=30")
+            .replace("rate=22", "rate=1")
+            .replace("rate=2;", "rate=17;");
+
+        for input in [INPUT, variant.as_str()] {
+            let input_data = parse_input(input).unwrap();
+            let network = ValveNetwork::new(&input_data, 0).unwrap();
+            assert_eq!(
+                network.solve(30),
+                brute_force::solve(&input_data, 0, 30, false)
+            );
+        }
+
+        // The brute force is too slow for the two actors on the variant
+        let input_data = parse_input(INPUT).unwrap();
+        assert_eq!(
+            ValveNetwork::new(&input_data, 0)
+                .unwrap()
+                .solve_with_elephant(26),
+            brute_force::solve(&input_data, 0, 26, true)
+        );
+    }
+
+    #[test]
+    fn test_schedule() {
+        let input_data = parse_input(INPUT).unwrap();
+        let network = ValveNetwork::new(&input_data, 0).unwrap();
+        let id = |name| input_data.id(name).unwrap();
+
+        let schedule = network.schedule(&input_data, 30, 1);
+        assert_eq!(
+            schedule.pressure_by_minute(&input_data).iter().sum::<u32>(),
+            1651
+        );
+        assert_eq!(
+            schedule.actions[0][..3],
+            [
+                Action::Move(id("DD")),
+                Action::Open(id("DD")),
+                Action::Move(id("CC"))
+            ]
+        );
+        let description = schedule.describe(&input_data);
+        assert!(description.contains(
+            "== Minute 5 ==\nValve DD is open, releasing 20 pressure.\nYou open valve BB."
+        ));
+        assert!(description.contains("Valves BB, DD, and JJ are open, releasing 54 pressure."));
+
+        let schedule = network.schedule(&input_data, 26, 2);
+        assert_eq!(
+            schedule.pressure_by_minute(&input_data).iter().sum::<u32>(),
+            1707
+        );
+        let description = schedule.describe(&input_data);
+        assert_eq!(description.matches("The elephant opens valve").count(), 3);
+        assert!(description.ends_with("Released so far: 1707\n"));
     }
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(puzzle_1(INPUT), "1651");
+        assert_eq!(puzzle_1(INPUT).unwrap(), "1651");
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(puzzle_2(INPUT), "1707");
+        assert_eq!(puzzle_2(INPUT).unwrap(), "1707");
     }
 }
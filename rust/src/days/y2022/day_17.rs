@@ -1,18 +1,18 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 
 use itertools::Itertools;
 
-use crate::structs::geometry::{point2, Point2, Vector2};
+use crate::{
+    algorithms::simulation::{simulation_cycle, Driver, Simulation},
+    render::{self, Color, Image},
+    structs::geometry::{point2, Direction4, Point2, Vector2},
+};
 
 type Point = Point2<i32>;
 type Vector = Vector2<i32>;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Direction {
-    Left,
-    Right,
-    Down,
-}
+// Rocks indexed by their highest point
+type FallenRocks = BTreeMap<i32, Vec<(Point, Rock)>>;
 
 #[derive(Debug, Clone)]
 struct Rock {
@@ -79,29 +79,22 @@ fn get_rock(number: u64) -> Rock {
     }
 }
 
-fn parse_input(input: &str) -> Vec<Direction> {
+fn parse_input(input: &str) -> Vec<Direction4> {
     input
         .chars()
-        .filter_map(|c| match c {
-            '>' => Some(Direction::Right),
-            '<' => Some(Direction::Left),
-            _ => None,
-        })
+        .filter_map(|c| Direction4::try_from(c).ok())
+        .filter(|d| d.is_horizontal())
         .collect()
 }
 
 // Returns the new position, if the rock is able to move
 fn rock_moves(
-    direction: Direction,
-    fallen_rocks: &BTreeMap<i32, Vec<(Point, Rock)>>,
+    direction: Direction4,
+    fallen_rocks: &FallenRocks,
     rock: &Rock,
     position: Point,
 ) -> Option<Point> {
-    let new_position = match direction {
-        Direction::Left => position - Point::unit_x(),
-        Direction::Right => position + Point::unit_x(),
-        Direction::Down => position - Point::unit_y(),
-    };
+    let new_position = position + direction.to_vector();
 
     if new_position.x < 0 || new_position.x + rock.offset.x >= 7 || new_position.y < 0 {
         return None;
@@ -125,115 +118,128 @@ fn rock_moves(
     Some(new_position)
 }
 
-fn get_floor(fallen_rocks: &BTreeMap<i32, Vec<(Point, Rock)>>) -> u8 {
-    let &max_height = fallen_rocks.keys().max().unwrap();
-
-    fallen_rocks
-        .range(max_height - 6..=max_height)
-        .flat_map(|(_, v)| v)
-        .flat_map(|(coord, rocks)| rocks.parts.iter().map(|r| *coord + *r))
-        .fold(0u8, |acc, coord| acc | (1u8 << (coord.x as u8)))
-}
-
-fn fall(
-    input: &Vec<Direction>,
+struct Chamber<'a> {
+    jets: &'a [Direction4],
+    jet_index: usize,
     rock_number: u64,
-) -> (BTreeMap<i32, Vec<(Point, Rock)>>, Option<(u64, i32, u64)>) {
-    let mut highest_point = 0; // floor height
-
-    let input_size = input.len();
-
-    // Rocks indexed by their highest point
-    let mut fallen_rocks: BTreeMap<i32, Vec<(Point, Rock)>> = BTreeMap::new();
-
-    let mut cycle_history: BTreeMap<u8, BTreeSet<(usize, usize)>> = BTreeMap::new();
-    let mut floor_history: BTreeMap<u8, (u64, i32)> = BTreeMap::new();
+    highest_point: i32, // floor height
+    fallen_rocks: FallenRocks,
+}
 
-    let mut cycle = None;
+impl<'a> Chamber<'a> {
+    fn new(jets: &'a [Direction4]) -> Self {
+        Self {
+            jets,
+            jet_index: 0,
+            rock_number: 0,
+            highest_point: 0,
+            fallen_rocks: BTreeMap::new(),
+        }
+    }
 
-    let mut i: usize = 0;
-    for rock_number in 1..=rock_number {
-        let rock = get_rock(rock_number);
+    // Drops the next rock and returns how much the tower grew
+    fn drop_rock(&mut self) -> i32 {
+        self.rock_number += 1;
+        let rock = get_rock(self.rock_number);
         let mut rock_position = Point {
             x: 2,
-            y: highest_point + 3,
+            y: self.highest_point + 3,
         };
 
         loop {
-            let direction = input[i % input_size];
-            i += 1;
-            if let Some(new_position) = rock_moves(direction, &fallen_rocks, &rock, rock_position) {
+            let direction = self.jets[self.jet_index];
+            self.jet_index = (self.jet_index + 1) % self.jets.len();
+            if let Some(new_position) =
+                rock_moves(direction, &self.fallen_rocks, &rock, rock_position)
+            {
                 rock_position = new_position;
             }
             if let Some(new_position) =
-                rock_moves(Direction::Down, &fallen_rocks, &rock, rock_position)
+                rock_moves(Direction4::Down, &self.fallen_rocks, &rock, rock_position)
             {
                 rock_position = new_position;
             } else {
+                let previous_height = self.highest_point;
                 let rock_top = rock_position.y + rock.offset.y + 1;
-                highest_point = highest_point.max(rock_top);
-                fallen_rocks
+                self.highest_point = self.highest_point.max(rock_top);
+                self.fallen_rocks
                     .entry(rock_position.y + rock.offset.y)
-                    .and_modify(|v| v.push((rock_position, rock.clone())))
-                    .or_insert(vec![(rock_position, rock.clone())]);
-                break;
+                    .or_default()
+                    .push((rock_position, rock));
+                return self.highest_point - previous_height;
             }
         }
-        let floor = get_floor(&fallen_rocks);
-        let cycle_key = (i % input_size, (rock_number % 5) as usize);
-        let cycle_detected = match cycle_history.entry(floor) {
-            std::collections::btree_map::Entry::Vacant(entry) => {
-                entry.insert(BTreeSet::from([cycle_key]));
-                None
+    }
+
+    // Distance from the top of the tower to the highest block of each column
+    fn column_depths(&self) -> [i32; 7] {
+        let mut column_tops = [-1; 7];
+        for (&rock_top, rocks) in self.fallen_rocks.iter().rev() {
+            if column_tops.iter().all(|&top| top >= rock_top) {
+                break;
             }
-            std::collections::btree_map::Entry::Occupied(mut entry) => {
-                match entry.get().contains(&cycle_key) {
-                    true => {
-                        let &last_floor_rock_number = floor_history.get(&floor).unwrap();
-                        Some((last_floor_rock_number, rock_number, cycle_key))
-                    }
-                    false => {
-                        entry.get_mut().insert(cycle_key);
-                        None
-                    }
+            for (position, rock) in rocks {
+                for part in &rock.parts {
+                    let block = *part + *position;
+                    let top = &mut column_tops[block.x as usize];
+                    *top = (*top).max(block.y);
                 }
             }
-        };
-        floor_history.insert(floor, (rock_number, highest_point));
-        if cycle.is_none() {
-            cycle = cycle_detected.and_then(|(last, current, _key)| match floor == 127 {
-                true => Some((current - last.0, highest_point - last.1, current)),
-                false => None,
-            });
         }
+        column_tops.map(|top| self.highest_point - top)
     }
 
-    (fallen_rocks, cycle)
+    // Two chambers with the same key evolve in the same way
+    fn state_key(&self) -> (usize, u64, [i32; 7]) {
+        (self.jet_index, self.rock_number % 5, self.column_depths())
+    }
+}
+
+// Each step drops a rock, observing how much the tower grew
+impl Simulation for Chamber<'_> {
+    type Observation = i32;
+
+    fn step(&mut self) -> i32 {
+        self.drop_rock()
+    }
+}
+
+fn fall(input: &[Direction4], rock_number: usize) -> Chamber<'_> {
+    let mut driver = Driver::new(Chamber::new(input));
+    driver.run(rock_number);
+    driver.into_simulation()
 }
 
 pub fn puzzle_1(input: &str) -> String {
     let input = parse_input(input);
 
-    (fall(&input, 2022).0.keys().max().unwrap() + 1).to_string()
+    let chamber = fall(&input, 2022);
+    render::export("2022_17_tower.ppm", || {
+        let points: Vec<_> = chamber
+            .fallen_rocks
+            .values()
+            .flatten()
+            .flat_map(|(position, rock)| rock.parts.iter().map(|p| *p + *position))
+            .collect();
+        Image::from_points(&points, Color::WHITE, Color::BLACK).flip_vertical()
+    });
+    chamber.highest_point.to_string()
 }
 
 pub fn puzzle_2(input: &str) -> String {
     let input = parse_input(input);
 
-    let max_len = 1_000_000_000_000;
-
-    let mut initial_guess = 10;
-    let (length, height, start) = loop {
-        match fall(&input, initial_guess).1 {
-            Some((length, height, start)) => break (length, height, start),
-            None => (),
-        }
-        initial_guess *= 2;
-    };
-    dbg!(length, height, start);
-    let remaining_cycles = (max_len - start) / length;
-    let result = remaining_cycles * height as u64;
-    result.to_string()
+    let rock_number = 1_000_000_000_000;
+
+    let trajectory = simulation_cycle(
+        Chamber::new(&input),
+        Chamber::state_key,
+        10 * 5 * input.len(),
+    );
+    trajectory
+        .extrapolate(rock_number, |&growth| growth as u64)
+        .unwrap()
+        .to_string()
 }
 
 #[cfg(test)]
@@ -248,7 +254,7 @@ mod tests {
         let parsed = parse_input(input);
         assert_eq!(
             parsed,
-            vec![Direction::Left, Direction::Right, Direction::Left]
+            vec![Direction4::Left, Direction4::Right, Direction4::Left]
         );
     }
 
@@ -1,18 +1,23 @@
-use core::panic;
 use std::{
     env,
     io::{self, Read},
+    path::Path,
+    process,
     time::Instant,
 };
 
 use days::get_days;
 use utils::Day;
 
+mod algorithms;
 mod days;
+mod ocr;
+mod parsing;
+mod render;
 mod structs;
 mod utils;
 
-fn execute(year: u16, day: &Day, puzzle_number: u8, input: &String) {
+fn execute(year: u16, day: &Day, puzzle_number: u8, input: &str) {
     let start = Instant::now();
     let puzzle = match puzzle_number {
         1 => day.puzzle_1,
@@ -20,7 +25,14 @@ fn execute(year: u16, day: &Day, puzzle_number: u8, input: &String) {
         _ => panic!(),
     };
 
-    let result = puzzle(input);
+    // Invalid inputs are reported without a backtrace
+    let result = match puzzle(input) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
 
     println!(
         "Year {:04} \t Day {:02} \t Time: {:.2e} s \t Puzzle {}: {}",
@@ -39,7 +51,19 @@ fn get_input() -> io::Result<String> {
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    if let Some(position) = args.iter().position(|a| a == "--render") {
+        let dir = args
+            .get(position + 1)
+            .expect("Expected a directory after --render")
+            .clone();
+        render::set_output_dir(Path::new(&dir)).expect("Cannot create render directory");
+        args.drain(position..=position + 1);
+    }
+    if let Some(position) = args.iter().position(|a| a == "--verbose") {
+        utils::set_verbose(true);
+        args.remove(position);
+    }
     let input = get_input().expect("Failed to parse input");
     let year = args
         .get(1)
@@ -0,0 +1,344 @@
+// Reading of the letters drawn by some puzzles, not every day uses all the entry points
+#![allow(dead_code)]
+
+use std::{error::Error, fmt};
+
+// Letters drawn by the puzzles, on 6 rows...
+const SMALL_FONT: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+// ...and on 10 rows
+const LARGE_FONT: &[(char, [&str; 10])] = &[
+    (
+        'A',
+        [
+            "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'B',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+            "#....#", "#####.",
+        ],
+    ),
+    (
+        'C',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#....#", ".####.",
+        ],
+    ),
+    (
+        'E',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'F',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'G',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+            "#...##", ".###.#",
+        ],
+    ),
+    (
+        'H',
+        [
+            "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'J',
+        [
+            "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+            "#...#.", ".###..",
+        ],
+    ),
+    (
+        'K',
+        [
+            "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+            "#...#.", "#....#",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'N',
+        [
+            "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+            "#...##", "#....#",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'R',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'X',
+        [
+            "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'Z',
+        [
+            "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnknownGlyph {
+    // Column of the image where the glyph starts
+    pub column: usize,
+    pub picture: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OcrError {
+    UnsupportedHeight(usize),
+    // The text has a `?` for each unknown glyph
+    UnknownGlyphs {
+        text: String,
+        glyphs: Vec<UnknownGlyph>,
+    },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::UnsupportedHeight(height) => {
+                write!(f, "no font has letters {} pixels high", height)
+            }
+            OcrError::UnknownGlyphs { text, glyphs } => {
+                write!(f, "unknown glyphs in `{}`", text)?;
+                for glyph in glyphs {
+                    write!(f, "\ncolumn {}:\n{}", glyph.column, glyph.picture)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for OcrError {}
+
+type Glyph = Vec<Vec<bool>>;
+
+// Blank columns around the glyph are not part of it
+fn trim_columns(rows: &[Vec<bool>]) -> Glyph {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let is_blank = |x: usize| rows.iter().all(|row| !row.get(x).copied().unwrap_or(false));
+    let Some(first) = (0..width).find(|&x| !is_blank(x)) else {
+        return vec![vec![]; rows.len()];
+    };
+    let last = (0..width).rfind(|&x| !is_blank(x)).unwrap();
+    rows.iter()
+        .map(|row| {
+            (first..=last)
+                .map(|x| row.get(x).copied().unwrap_or(false))
+                .collect()
+        })
+        .collect()
+}
+
+fn font_glyph(rows: &[&str]) -> Glyph {
+    let rows: Vec<Vec<bool>> = rows
+        .iter()
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect();
+    trim_columns(&rows)
+}
+
+fn font(height: usize) -> Option<Vec<(char, Glyph)>> {
+    match height {
+        6 => Some(
+            SMALL_FONT
+                .iter()
+                .map(|(c, g)| (*c, font_glyph(g)))
+                .collect(),
+        ),
+        10 => Some(
+            LARGE_FONT
+                .iter()
+                .map(|(c, g)| (*c, font_glyph(g)))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn format_glyph(glyph: &Glyph) -> String {
+    glyph
+        .iter()
+        .map(|row| row.iter().map(|&p| if p { '#' } else { '.' }).collect())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Letters are separated by blank columns, and blank rows around the text are ignored
+pub fn recognize(pixels: &[Vec<bool>]) -> Result<String, OcrError> {
+    let first_row = pixels.iter().position(|row| row.contains(&true));
+    let last_row = pixels.iter().rposition(|row| row.contains(&true));
+    let rows = match (first_row, last_row) {
+        (Some(first), Some(last)) => &pixels[first..=last],
+        _ => return Ok(String::new()),
+    };
+    let font = font(rows.len()).ok_or(OcrError::UnsupportedHeight(rows.len()))?;
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let is_blank = |x: usize| rows.iter().all(|row| !row.get(x).copied().unwrap_or(false));
+    let mut text = String::new();
+    let mut unknown = vec![];
+    let mut x = 0;
+    while x < width {
+        if is_blank(x) {
+            x += 1;
+            continue;
+        }
+        let end = (x..width).find(|&x| is_blank(x)).unwrap_or(width);
+        let columns: Vec<Vec<bool>> = rows
+            .iter()
+            .map(|row| {
+                (x..end)
+                    .map(|x| row.get(x).copied().unwrap_or(false))
+                    .collect()
+            })
+            .collect();
+        let glyph = trim_columns(&columns);
+        match font.iter().find(|(_, g)| *g == glyph) {
+            Some((letter, _)) => text.push(*letter),
+            None => {
+                text.push('?');
+                unknown.push(UnknownGlyph {
+                    column: x,
+                    picture: format_glyph(&glyph),
+                });
+            }
+        }
+        x = end;
+    }
+
+    match unknown.is_empty() {
+        true => Ok(text),
+        false => Err(OcrError::UnknownGlyphs {
+            text,
+            glyphs: unknown,
+        }),
+    }
+}
+
+// Same as `recognize`, lit pixels being drawn with `#`
+pub fn recognize_str(picture: &str) -> Result<String, OcrError> {
+    let pixels: Vec<Vec<bool>> = picture
+        .lines()
+        .map(|line| line.chars().map(|c| c == '#').collect())
+        .collect();
+    recognize(&pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Draws the text with one blank column between the letters
+    fn draw<const H: usize>(font: &[(char, [&str; H])], text: &str) -> String {
+        let glyphs: Vec<_> = text
+            .chars()
+            .map(|c| font.iter().find(|(l, _)| *l == c).unwrap().1)
+            .collect();
+        (0..H)
+            .map(|y| glyphs.iter().map(|g| g[y]).collect::<Vec<_>>().join("."))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_small_font() {
+        let letters: String = SMALL_FONT.iter().map(|(c, _)| c).collect();
+        assert_eq!(recognize_str(&draw(SMALL_FONT, &letters)), Ok(letters));
+        let picture = format!("........\n{}\n", draw(SMALL_FONT, "ZIP"));
+        assert_eq!(recognize_str(&picture), Ok("ZIP".to_string()));
+    }
+
+    #[test]
+    fn test_large_font() {
+        let letters: String = LARGE_FONT.iter().map(|(c, _)| c).collect();
+        assert_eq!(recognize_str(&draw(LARGE_FONT, &letters)), Ok(letters));
+    }
+
+    #[test]
+    fn test_unknown_glyph() {
+        let picture = draw(SMALL_FONT, "AB")
+            .lines()
+            .map(|line| format!("{}.###", line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let error = recognize_str(&picture).unwrap_err();
+        assert_eq!(
+            error,
+            OcrError::UnknownGlyphs {
+                text: "AB?".to_string(),
+                glyphs: vec![UnknownGlyph {
+                    column: 10,
+                    picture: "###\n###\n###\n###\n###\n###".to_string(),
+                }],
+            }
+        );
+        assert!(error
+            .to_string()
+            .starts_with("unknown glyphs in `AB?`\ncolumn 10:\n###"));
+
+        assert_eq!(
+            recognize_str("#\n#\n#"),
+            Err(OcrError::UnsupportedHeight(3))
+        );
+    }
+}
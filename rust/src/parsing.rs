@@ -0,0 +1,379 @@
+// Parsers shared by the days, each day only needs some of them
+#![allow(dead_code)]
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use nom::{
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, digit1, one_of},
+    combinator::{all_consuming, map_res, opt, recognize},
+    error::Error as NomError,
+    multi::separated_list1,
+    sequence::{pair, preceded, separated_pair},
+    IResult,
+};
+
+use num::Integer;
+
+use crate::structs::geometry::Point2;
+
+// Line and column numbers start at 1
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: Option<usize>,
+    pub message: String,
+    // Name of the construct that was being parsed
+    pub expected: Option<String>,
+    // Text of the offending line
+    pub snippet: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column: None,
+            message: message.into(),
+            expected: None,
+            snippet: None,
+        }
+    }
+
+    pub fn at(line: usize, line_text: &str, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            column: Some(column),
+            snippet: Some(line_text.to_string()),
+            ..Self::new(line, message)
+        }
+    }
+
+    // The innermost construct is kept when errors are wrapped several times
+    pub fn expecting(mut self, construct: &str) -> Self {
+        self.expected.get_or_insert_with(|| construct.to_string());
+        self
+    }
+
+    // `input` starts at line `first_line`, and the input of the error is a slice of it
+    pub fn from_nom(first_line: usize, input: &str, error: nom::Err<NomError<&str>>) -> Self {
+        let rest = match error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => error.input,
+            nom::Err::Incomplete(_) => "",
+        };
+        let offset = (rest.as_ptr() as usize)
+            .checked_sub(input.as_ptr() as usize)
+            .filter(|&offset| offset <= input.len())
+            .unwrap_or(input.len() - rest.len().min(input.len()));
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_text = input[line_start..].lines().next().unwrap_or("");
+        let line = first_line + input[..offset].matches('\n').count();
+        let column = input[line_start..offset].chars().count() + 1;
+
+        // Words are reported whole, other characters one at a time
+        let token = match rest.find(|c: char| !c.is_alphanumeric()) {
+            Some(0) => &rest[..rest.chars().next().map_or(0, char::len_utf8)],
+            Some(end) => &rest[..end],
+            None => rest,
+        };
+        let message = match token {
+            "" => "unexpected end of input".to_string(),
+            "\n" => "unexpected end of line".to_string(),
+            token => format!("unexpected `{}`", token),
+        };
+        Self::at(line, line_text, column, message)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error: {}", self.message)?;
+        if let Some(expected) = &self.expected {
+            write!(f, ", expected {}", expected)?;
+        }
+        match self.column {
+            Some(column) => write!(f, "\n --> line {}, column {}", self.line, column)?,
+            None => write!(f, "\n --> line {}", self.line)?,
+        }
+        if let Some(snippet) = &self.snippet {
+            let margin = " ".repeat(self.line.to_string().len());
+            write!(f, "\n{} |\n{} | {}", margin, self.line, snippet)?;
+            if let Some(column) = self.column {
+                write!(f, "\n{} | {}^", margin, " ".repeat(column - 1))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+// Integer with an optional sign
+pub fn signed<T>(input: &str) -> IResult<&str, T>
+where
+    T: FromStr,
+{
+    map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse)(input)
+}
+
+// Integers separated by any non-empty run of the given separator characters
+pub fn integer_list<'a, T>(
+    separators: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>>
+where
+    T: FromStr,
+{
+    separated_list1(take_while1(move |c| separators.contains(c)), signed)
+}
+
+// `x=1, y=-2`
+pub fn coordinates<T>(input: &str) -> IResult<&str, Point2<T>>
+where
+    T: Integer + FromStr,
+{
+    let (input, (x, y)) = separated_pair(
+        preceded(tag("x="), signed),
+        pair(char(','), opt(char(' '))),
+        preceded(tag("y="), signed),
+    )(input)?;
+    Ok((input, Point2 { x, y }))
+}
+
+// `key: value`, both sides being trimmed
+pub fn key_value(input: &str) -> IResult<&str, (&str, &str)> {
+    let (value, key) = take_while1(|c| c != ':')(input)?;
+    let (value, _) = char(':')(value)?;
+    Ok(("", (key.trim(), value.trim())))
+}
+
+// Column of `part`, which must be a slice of `line`
+pub fn column_of(line: &str, part: &str) -> usize {
+    let offset = part.as_ptr() as usize - line.as_ptr() as usize;
+    line[..offset].chars().count() + 1
+}
+
+// Runs the parser on the whole line
+pub fn parse_line<'a, T>(
+    line_number: usize,
+    line: &'a str,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+) -> ParseResult<T> {
+    match all_consuming(parser)(line) {
+        Ok((_, value)) => Ok(value),
+        Err(error) => Err(ParseError::from_nom(line_number, line, error)),
+    }
+}
+
+// Runs the parser on a block of lines starting at line `first_line`
+pub fn parse_block<'a, T>(
+    first_line: usize,
+    block: &'a str,
+    expected: &str,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+) -> ParseResult<T> {
+    match all_consuming(parser)(block) {
+        Ok((_, value)) => Ok(value),
+        Err(error) => Err(ParseError::from_nom(first_line, block, error).expecting(expected)),
+    }
+}
+
+// Runs the parser on the whole input
+pub fn parse_all<'a, T>(
+    input: &'a str,
+    expected: &str,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+) -> ParseResult<T> {
+    parse_block(1, input.trim_end(), expected, parser)
+}
+
+// Parses each non-empty line, `first_line` being the number of the first one
+pub fn parse_lines_from<'a, T>(
+    first_line: usize,
+    input: &'a str,
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+) -> ParseResult<Vec<T>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_line(first_line + i, line, &mut parser))
+        .collect()
+}
+
+pub fn parse_lines<'a, T>(
+    input: &'a str,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+) -> ParseResult<Vec<T>> {
+    parse_lines_from(1, input, parser)
+}
+
+// Blocks separated by blank lines, with the number of their first line
+pub fn blocks(input: &str) -> Vec<(usize, &str)> {
+    let mut blocks = vec![];
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+
+    for (i, line) in input.split_inclusive('\n').enumerate() {
+        let is_blank = line.trim().is_empty();
+        match (start, is_blank) {
+            (None, false) => start = Some((i + 1, offset)),
+            (Some((line_number, from)), true) => {
+                blocks.push((line_number, input[from..offset].trim_end()));
+                start = None;
+            }
+            _ => (),
+        }
+        offset += line.len();
+    }
+    if let Some((line_number, from)) = start {
+        blocks.push((line_number, input[from..].trim_end()));
+    }
+    blocks
+}
+
+// All the integers found in the line, whatever is around them. A minus sign is only
+// part of a number when it does not directly follow a digit, so that `2-4` is 2 and 4.
+pub fn integers<T>(line: &str) -> Vec<T>
+where
+    T: FromStr,
+{
+    let bytes = line.as_bytes();
+    let mut numbers = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let is_sign = bytes[i] == b'-'
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)
+            && (i == 0 || !bytes[i - 1].is_ascii_digit());
+        if !is_sign && !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        numbers.extend(line[start..i].parse().ok());
+    }
+    numbers
+}
+
+// Every line must have the same width, `cell` rejecting unknown characters
+pub fn grid<T>(input: &str, cell: impl Fn(char) -> Option<T>) -> ParseResult<Vec<Vec<T>>> {
+    let mut rows: Vec<Vec<T>> = vec![];
+    for (i, line) in input.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let row = line
+            .chars()
+            .enumerate()
+            .map(|(column, c)| {
+                cell(c).ok_or_else(|| {
+                    ParseError::at(i + 1, line, column + 1, format!("unexpected `{}`", c))
+                })
+            })
+            .collect::<ParseResult<Vec<_>>>()?;
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(ParseError::at(
+                    i + 1,
+                    line,
+                    row.len().min(first.len()) + 1,
+                    format!("expected {} cells, found {}", first.len(), row.len()),
+                ));
+            }
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+pub fn char_grid(input: &str) -> ParseResult<Vec<Vec<char>>> {
+    grid(input, Some)
+}
+
+// `key: value` lines, `first_line` being the number of the first one
+pub fn records(first_line: usize, input: &str) -> ParseResult<Vec<(&str, &str)>> {
+    parse_lines_from(first_line, input, key_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::geometry::point2;
+
+    #[test]
+    fn test_blocks() {
+        let input = "1\n2\n\n3\n\n\n4\n5\n";
+        assert_eq!(blocks(input), vec![(1, "1\n2"), (4, "3"), (7, "4\n5")]);
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(integers::<i32>("move 1 from 22 to -3"), vec![1, 22, -3]);
+        assert_eq!(integers::<u32>("2-4,6-8"), vec![2, 4, 6, 8]);
+        assert_eq!(
+            integer_list::<i32>(", ")("1, -2,3 4"),
+            Ok(("", vec![1, -2, 3, 4]))
+        );
+    }
+
+    #[test]
+    fn test_coordinates() {
+        assert_eq!(coordinates::<i32>("x=2, y=-18"), Ok(("", point2!(2, -18))));
+        assert_eq!(coordinates::<i32>("x=2,y=18"), Ok(("", point2!(2, 18))));
+    }
+
+    #[test]
+    fn test_records() {
+        let input = "Monkey 0:\n  Starting items: 79, 98\n  Test: divisible by 23";
+        let (header, rest) = input.split_once('\n').unwrap();
+        assert_eq!(key_value(header), Ok(("", ("Monkey 0", ""))));
+        assert_eq!(
+            records(2, rest),
+            Ok(vec![
+                ("Starting items", "79, 98"),
+                ("Test", "divisible by 23")
+            ])
+        );
+        assert_eq!(
+            records(2, "no colon"),
+            Err(ParseError::at(2, "no colon", 9, "unexpected end of input"))
+        );
+    }
+
+    #[test]
+    fn test_grid() {
+        let digit = |c: char| c.to_digit(10);
+        assert_eq!(grid("12\n34\n", digit), Ok(vec![vec![1, 2], vec![3, 4]]));
+        assert_eq!(
+            grid("12\n3x", digit),
+            Err(ParseError::at(2, "3x", 2, "unexpected `x`"))
+        );
+        assert_eq!(grid("12\n3", digit).unwrap_err().line, 2);
+        assert_eq!(char_grid("ab").unwrap(), vec![vec!['a', 'b']]);
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let parsed = parse_lines("1 2\n\n3 x\n", integer_list::<i32>(" "));
+        assert_eq!(parsed, Err(ParseError::at(3, "3 x", 2, "unexpected ` `")));
+    }
+
+    #[test]
+    fn test_report() {
+        let pair = separated_pair(signed::<u8>, char('\n'), signed::<u8>);
+        let error = parse_all("12\n3x", "number", pair).unwrap_err();
+        assert_eq!((error.line, error.column), (2, Some(2)));
+        assert_eq!(
+            error.to_string(),
+            "error: unexpected `x`, expected number\n --> line 2, column 2\n  |\n2 | 3x\n  |  ^"
+        );
+        assert_eq!(
+            ParseError::new(12, "missing block").to_string(),
+            "error: missing block\n --> line 12"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+// Image helpers for the days that can draw their state, not all of them are used yet
+#![allow(dead_code)]
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use num::{Integer, ToPrimitive};
+
+use crate::structs::geometry::Point2;
+
+static OUTPUT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    pub const BLACK: Color = Color(0, 0, 0);
+    pub const WHITE: Color = Color(255, 255, 255);
+
+    pub fn gray(&self) -> u8 {
+        let Color(r, g, b) = *self;
+        ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+    }
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+// Palette for boolean grids: set cells are drawn in black
+pub fn black_on_white(value: &bool) -> Color {
+    match value {
+        true => Color::BLACK,
+        false => Color::WHITE,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Color> {
+        match x < self.width && y < self.height {
+            true => Some(self.pixels[y * self.width + x]),
+            false => None,
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    // Rows of the grid are drawn from top to bottom
+    pub fn from_grid<T>(grid: &[Vec<T>], palette: impl Fn(&T) -> Color) -> Self {
+        let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut image = Self::new(width, grid.len(), Color::WHITE);
+        for (y, row) in grid.iter().enumerate() {
+            for (x, value) in row.iter().enumerate() {
+                image.set(x, y, palette(value));
+            }
+        }
+        image
+    }
+
+    // The image spans the bounding box of the cells, y growing downwards
+    pub fn from_sparse<S, T>(
+        cells: &BTreeMap<Point2<S>, T>,
+        background: Color,
+        palette: impl Fn(&T) -> Color,
+    ) -> Self
+    where
+        S: Integer + ToPrimitive + Copy,
+    {
+        let coords: Vec<_> = cells
+            .iter()
+            .filter_map(|(point, value)| Some((point.x.to_i64()?, point.y.to_i64()?, value)))
+            .collect();
+        let Some(min_x) = coords.iter().map(|c| c.0).min() else {
+            return Self::new(0, 0, background);
+        };
+        let max_x = coords.iter().map(|c| c.0).max().unwrap();
+        let min_y = coords.iter().map(|c| c.1).min().unwrap();
+        let max_y = coords.iter().map(|c| c.1).max().unwrap();
+
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+        let mut image = Self::new(width, height, background);
+        for (x, y, value) in coords {
+            image.set((x - min_x) as usize, (y - min_y) as usize, palette(value));
+        }
+        image
+    }
+
+    pub fn from_points<'a, S>(
+        points: impl IntoIterator<Item = &'a Point2<S>>,
+        background: Color,
+        foreground: Color,
+    ) -> Self
+    where
+        S: Integer + ToPrimitive + Copy + 'a,
+    {
+        let cells: BTreeMap<_, _> = points.into_iter().map(|p| (*p, ())).collect();
+        Self::from_sparse(&cells, background, |_| foreground)
+    }
+
+    // For coordinates where y grows upwards
+    pub fn flip_vertical(mut self) -> Self {
+        let width = self.width;
+        let rows: Vec<_> = self
+            .pixels
+            .chunks(width.max(1))
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        self.pixels = rows;
+        self
+    }
+
+    pub fn scale(&self, factor: usize) -> Self {
+        let mut image = Self::new(self.width * factor, self.height * factor, Color::WHITE);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                image.pixels[y * image.width + x] =
+                    self.pixels[(y / factor) * self.width + x / factor];
+            }
+        }
+        image
+    }
+
+    pub fn write_ppm(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let data: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|Color(r, g, b)| [*r, *g, *b])
+            .collect();
+        writer.write_all(&data)
+    }
+
+    pub fn write_pgm(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        let data: Vec<u8> = self.pixels.iter().map(Color::gray).collect();
+        writer.write_all(&data)
+    }
+
+    // Pixels are grouped in horizontal runs of the same color to keep the file small
+    pub fn write_svg(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            self.width, self.height
+        )?;
+        for (y, row) in self.pixels.chunks(self.width.max(1)).enumerate() {
+            let mut x = 0;
+            for run in row.chunk_by(|a, b| a == b) {
+                writeln!(
+                    writer,
+                    r#"<rect x="{}" y="{}" width="{}" height="1" fill="{}"/>"#,
+                    x,
+                    y,
+                    run.len(),
+                    run[0].hex()
+                )?;
+                x += run.len();
+            }
+        }
+        writeln!(writer, "</svg>")
+    }
+
+    // The format is chosen from the extension: ppm, pgm or svg
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let write: fn(&Self, &mut BufWriter<File>) -> io::Result<()> =
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("ppm") => Self::write_ppm,
+                Some("pgm") => Self::write_pgm,
+                Some("svg") => Self::write_svg,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("unknown image format for {}", path.display()),
+                    ))
+                }
+            };
+        let mut writer = BufWriter::new(File::create(path)?);
+        write(self, &mut writer)
+    }
+}
+
+pub fn set_output_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    OUTPUT_DIR.get_or_init(|| dir.to_path_buf());
+    Ok(())
+}
+
+// Saves the image in the output directory, only built when rendering was requested
+pub fn export(file_name: &str, image: impl FnOnce() -> Image) {
+    if let Some(dir) = OUTPUT_DIR.get() {
+        let path = dir.join(file_name);
+        if let Err(error) = image().save(&path) {
+            eprintln!("Cannot render {}: {}", path.display(), error);
+        }
+    }
+}
+
+// Same as `export`, for text renderings
+pub fn export_text(file_name: &str, text: impl FnOnce() -> String) {
+    if let Some(dir) = OUTPUT_DIR.get() {
+        let path = dir.join(file_name);
+        if let Err(error) = fs::write(&path, text()) {
+            eprintln!("Cannot render {}: {}", path.display(), error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::geometry::point2;
+
+    #[test]
+    fn test_from_points() {
+        let points = [point2!(-1, 2), point2!(1, 3)];
+        let image = Image::from_points(&points, Color::WHITE, Color::BLACK);
+
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert_eq!(image.get(0, 0), Some(Color::BLACK));
+        assert_eq!(image.get(2, 1), Some(Color::BLACK));
+        assert_eq!(image.get(1, 0), Some(Color::WHITE));
+        assert_eq!(image.flip_vertical().get(0, 1), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn test_write_formats() {
+        let image = Image::from_grid(&[vec![true, false], vec![false, false]], black_on_white);
+
+        let mut ppm = vec![];
+        image.write_ppm(&mut ppm).unwrap();
+        assert_eq!(&ppm[..11], b"P6\n2 2\n255\n");
+        assert_eq!(&ppm[11..17], &[0, 0, 0, 255, 255, 255]);
+
+        let mut pgm = vec![];
+        image.write_pgm(&mut pgm).unwrap();
+        assert_eq!(&pgm[11..], &[0, 255, 255, 255]);
+
+        let mut svg = vec![];
+        image.write_svg(&mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert_eq!(svg.matches("<rect").count(), 3);
+    }
+
+    #[test]
+    fn test_save_unknown_format() {
+        let path = std::env::temp_dir().join(format!("avc_render_{}.bmp", std::process::id()));
+        let error = Image::new(1, 1, Color::WHITE).save(&path).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+}
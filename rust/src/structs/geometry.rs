@@ -1,8 +1,13 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::{
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
 
-use num::{Integer, Signed};
+use num::{Bounded, Integer, Signed};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+use super::interval::{Interval, RangeSet};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub struct Point2<S>
 where
     S: Integer,
@@ -20,10 +25,29 @@ pub(crate) use point2;
 
 pub type Vector2<S> = Point2<S>;
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Point3<S>
+where
+    S: Integer,
+{
+    pub x: S,
+    pub y: S,
+    pub z: S,
+}
+
+pub type Vector3<S> = Point3<S>;
+
 impl<S> Point2<S>
 where
     S: Integer + Copy,
 {
+    pub fn zero() -> Self {
+        Point2 {
+            x: S::zero(),
+            y: S::zero(),
+        }
+    }
+
     pub fn unit_x() -> Self {
         Point2 {
             x: S::one(),
@@ -50,6 +74,21 @@ where
     pub fn distance_1(&self, rhs: &Self) -> S {
         (self.x - rhs.x).abs() + (self.y - rhs.y).abs()
     }
+
+    // Coordinates (x + y, x - y), in which L1 balls are axis-aligned squares
+    pub fn rotate_45(&self) -> Self {
+        point2!(self.x + self.y, self.x - self.y)
+    }
+
+    // Inverse of `rotate_45`, only defined when both rotated coordinates have the same parity
+    pub fn unrotate_45(&self) -> Option<Self> {
+        let two = S::one() + S::one();
+        let sum = self.x + self.y;
+        match sum.is_even() {
+            true => Some(point2!(sum / two, (self.x - self.y) / two)),
+            false => None,
+        }
+    }
 }
 
 impl<S> Add for Point2<S>
@@ -240,3 +279,755 @@ where
         }
     }
 }
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rect<S>
+where
+    S: Integer,
+{
+    pub min: Point2<S>,
+    pub max: Point2<S>,
+}
+
+impl<S> Rect<S>
+where
+    S: Integer + Copy,
+{
+    pub fn new(min: Point2<S>, max: Point2<S>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, point: &Point2<S>) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let rect = Self {
+            min: point2!(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            max: point2!(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        };
+        match rect.is_empty() {
+            true => None,
+            false => Some(rect),
+        }
+    }
+}
+
+impl<S> Rect<S>
+where
+    S: Integer + Bounded + Copy,
+{
+    pub fn max_bounds() -> Self {
+        Self {
+            min: point2!(S::min_value(), S::min_value()),
+            max: point2!(S::max_value(), S::max_value()),
+        }
+    }
+}
+
+fn shift_coordinate<S>(value: S, delta: i8, min: S, max: S) -> Option<S>
+where
+    S: Integer + Copy,
+{
+    match delta {
+        -1 if value > min => Some(value - S::one()),
+        1 if value < max => Some(value + S::one()),
+        0 => Some(value),
+        _ => None,
+    }
+}
+
+fn delta_to_scalar<S>(delta: i8) -> S
+where
+    S: Integer + Signed,
+{
+    match delta {
+        -1 => -S::one(),
+        1 => S::one(),
+        _ => S::zero(),
+    }
+}
+
+fn scalar_to_delta<S>(value: S) -> Option<i8>
+where
+    S: Integer + Signed,
+{
+    if value.is_zero() {
+        Some(0)
+    } else if value.is_one() {
+        Some(1)
+    } else if (-value).is_one() {
+        Some(-1)
+    } else {
+        None
+    }
+}
+
+impl<S> Point2<S>
+where
+    S: Integer + Copy,
+{
+    // Moves one step in the given direction, or returns None when leaving the bounds
+    pub fn checked_step<D>(self, direction: D, bounds: &Rect<S>) -> Option<Self>
+    where
+        D: Into<Direction8>,
+        S: Bounded,
+    {
+        let (dx, dy) = direction.into().delta();
+        self.checked_shift(dx, dy, bounds)
+    }
+
+    // Same as `checked_step`, for grids whose y grows downwards like the lines of a text
+    pub fn checked_step_y_down<D>(self, direction: D, bounds: &Rect<S>) -> Option<Self>
+    where
+        D: Into<Direction8>,
+        S: Bounded,
+    {
+        let (dx, dy) = direction.into().delta();
+        self.checked_shift(dx, -dy, bounds)
+    }
+
+    fn checked_shift(self, dx: i8, dy: i8, bounds: &Rect<S>) -> Option<Self>
+    where
+        S: Bounded,
+    {
+        let next = point2!(
+            shift_coordinate(self.x, dx, S::min_value(), S::max_value())?,
+            shift_coordinate(self.y, dy, S::min_value(), S::max_value())?
+        );
+        bounds.contains(&next).then_some(next)
+    }
+
+    // Moves one step in the given direction, each coordinate stopping at the bounds
+    pub fn saturating_step<D>(self, direction: D, bounds: &Rect<S>) -> Self
+    where
+        D: Into<Direction8>,
+    {
+        let (dx, dy) = direction.into().delta();
+        point2!(
+            shift_coordinate(self.x, dx, bounds.min.x, bounds.max.x).unwrap_or(self.x),
+            shift_coordinate(self.y, dy, bounds.min.y, bounds.max.y).unwrap_or(self.y)
+        )
+    }
+}
+
+// Directions follow the orientation of `Vector2::unit_y()`: `Up` increases y
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Direction4 {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction4 {
+    pub const ALL: [Direction4; 4] = [
+        Direction4::Up,
+        Direction4::Right,
+        Direction4::Down,
+        Direction4::Left,
+    ];
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction4::Up => Direction4::Right,
+            Direction4::Right => Direction4::Down,
+            Direction4::Down => Direction4::Left,
+            Direction4::Left => Direction4::Up,
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.turn_right().turn_around()
+    }
+
+    pub fn turn_around(self) -> Self {
+        self.turn_right().turn_right()
+    }
+
+    pub fn is_opposite(self, other: Self) -> bool {
+        self.turn_around() == other
+    }
+
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Direction4::Left | Direction4::Right)
+    }
+
+    pub fn to_vector<S>(self) -> Vector2<S>
+    where
+        S: Integer + Signed,
+    {
+        Direction8::from(self).to_vector()
+    }
+}
+
+impl TryFrom<char> for Direction4 {
+    type Error = ();
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            'U' | 'N' | '^' | '↑' => Ok(Direction4::Up),
+            'R' | 'E' | '>' | '→' => Ok(Direction4::Right),
+            'D' | 'S' | 'v' | '↓' => Ok(Direction4::Down),
+            'L' | 'W' | '<' | '←' => Ok(Direction4::Left),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Direction4 {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Direction4::try_from(c),
+            _ => Err(()),
+        }
+    }
+}
+
+impl<S> TryFrom<Vector2<S>> for Direction4
+where
+    S: Integer + Signed,
+{
+    type Error = ();
+
+    fn try_from(value: Vector2<S>) -> Result<Self, Self::Error> {
+        match Direction8::try_from(value)? {
+            Direction8::Up => Ok(Direction4::Up),
+            Direction8::Right => Ok(Direction4::Right),
+            Direction8::Down => Ok(Direction4::Down),
+            Direction8::Left => Ok(Direction4::Left),
+            _ => Err(()),
+        }
+    }
+}
+
+impl<S> From<Direction4> for Vector2<S>
+where
+    S: Integer + Signed,
+{
+    fn from(value: Direction4) -> Self {
+        value.to_vector()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Direction8 {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction8 {
+    pub const ALL: [Direction8; 8] = [
+        Direction8::Up,
+        Direction8::UpRight,
+        Direction8::Right,
+        Direction8::DownRight,
+        Direction8::Down,
+        Direction8::DownLeft,
+        Direction8::Left,
+        Direction8::UpLeft,
+    ];
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&d| d == self).unwrap()
+    }
+
+    // Turns by 45° steps, clockwise for positive values
+    pub fn rotate(self, steps: i32) -> Self {
+        Self::ALL[(self.index() as i32 + steps).rem_euclid(8) as usize]
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate(2)
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.rotate(-2)
+    }
+
+    pub fn turn_around(self) -> Self {
+        self.rotate(4)
+    }
+
+    pub fn is_opposite(self, other: Self) -> bool {
+        self.turn_around() == other
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self.index() % 2 == 1
+    }
+
+    fn delta(self) -> (i8, i8) {
+        match self {
+            Direction8::Up => (0, 1),
+            Direction8::UpRight => (1, 1),
+            Direction8::Right => (1, 0),
+            Direction8::DownRight => (1, -1),
+            Direction8::Down => (0, -1),
+            Direction8::DownLeft => (-1, -1),
+            Direction8::Left => (-1, 0),
+            Direction8::UpLeft => (-1, 1),
+        }
+    }
+
+    pub fn to_vector<S>(self) -> Vector2<S>
+    where
+        S: Integer + Signed,
+    {
+        let (dx, dy) = self.delta();
+        point2!(delta_to_scalar(dx), delta_to_scalar(dy))
+    }
+}
+
+impl From<Direction4> for Direction8 {
+    fn from(value: Direction4) -> Self {
+        match value {
+            Direction4::Up => Direction8::Up,
+            Direction4::Right => Direction8::Right,
+            Direction4::Down => Direction8::Down,
+            Direction4::Left => Direction8::Left,
+        }
+    }
+}
+
+impl TryFrom<char> for Direction8 {
+    type Error = ();
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '↗' => Ok(Direction8::UpRight),
+            '↘' => Ok(Direction8::DownRight),
+            '↙' => Ok(Direction8::DownLeft),
+            '↖' => Ok(Direction8::UpLeft),
+            c => Direction4::try_from(c).map(Direction8::from),
+        }
+    }
+}
+
+impl FromStr for Direction8 {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NE" | "UR" => Ok(Direction8::UpRight),
+            "SE" | "DR" => Ok(Direction8::DownRight),
+            "SW" | "DL" => Ok(Direction8::DownLeft),
+            "NW" | "UL" => Ok(Direction8::UpLeft),
+            s => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Direction8::try_from(c),
+                    _ => Err(()),
+                }
+            }
+        }
+    }
+}
+
+impl<S> TryFrom<Vector2<S>> for Direction8
+where
+    S: Integer + Signed,
+{
+    type Error = ();
+
+    fn try_from(value: Vector2<S>) -> Result<Self, Self::Error> {
+        let delta = (
+            scalar_to_delta(value.x).ok_or(())?,
+            scalar_to_delta(value.y).ok_or(())?,
+        );
+        Direction8::ALL
+            .into_iter()
+            .find(|d| d.delta() == delta)
+            .ok_or(())
+    }
+}
+
+impl<S> From<Direction8> for Vector2<S>
+where
+    S: Integer + Signed,
+{
+    fn from(value: Direction8) -> Self {
+        value.to_vector()
+    }
+}
+
+// Set of points within a L1 distance of a center
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Diamond<S>
+where
+    S: Integer,
+{
+    pub center: Point2<S>,
+    pub radius: S,
+}
+
+#[derive(Clone, Copy)]
+enum RotatedAxis {
+    U,
+    V,
+}
+
+impl<S> Diamond<S>
+where
+    S: Integer + Signed + Copy,
+{
+    pub fn new(center: Point2<S>, radius: S) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains(&self, point: &Point2<S>) -> bool {
+        self.center.distance_1(point) <= self.radius
+    }
+
+    // Square covering the diamond in the coordinates of `Point2::rotate_45`
+    pub fn to_rotated(self) -> Rect<S> {
+        let center = self.center.rotate_45();
+        let radius = point2!(self.radius, self.radius);
+        Rect::new(center - radius, center + radius)
+    }
+
+    pub fn from_rotated(rect: &Rect<S>) -> Option<Self> {
+        let two = S::one() + S::one();
+        let size = rect.max - rect.min;
+        if size.x != size.y || size.x.is_negative() || size.x.is_odd() {
+            return None;
+        }
+        let center = (rect.min + rect.max) / two;
+        Some(Self {
+            center: center.unrotate_45()?,
+            radius: size.x / two,
+        })
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.center.distance_1(&other.center) <= self.radius + other.radius
+    }
+
+    // The intersection of two diamonds is a rectangle in rotated coordinates
+    pub fn intersection(&self, other: &Self) -> Option<Rect<S>> {
+        self.to_rotated().intersection(&other.to_rotated())
+    }
+
+    pub fn intersects_rect(&self, rect: &Rect<S>) -> bool {
+        let closest = point2!(
+            self.center.x.clamp(rect.min.x, rect.max.x),
+            self.center.y.clamp(rect.min.y, rect.max.y)
+        );
+        !rect.is_empty() && self.contains(&closest)
+    }
+
+    // Points at a distance of exactly `radius + 1` from the center
+    pub fn outer_boundary(&self) -> impl Iterator<Item = Point2<S>> {
+        let center = self.center;
+        let outer = self.radius + S::one();
+        std::iter::successors(Some(S::zero()), |&i| Some(i + S::one()))
+            .take_while(move |&i| i < outer)
+            .flat_map(move |i| {
+                [
+                    center + point2!(i, outer - i),
+                    center + point2!(outer - i, -i),
+                    center + point2!(-i, i - outer),
+                    center + point2!(i - outer, i),
+                ]
+            })
+    }
+
+    // Returns a point of the rectangle which is in none of the diamonds
+    pub fn uncovered_point(diamonds: &[Self], rect: &Rect<S>) -> Option<Point2<S>> {
+        if rect.is_empty() {
+            return None;
+        }
+        if !diamonds.iter().any(|d| d.intersects_rect(rect)) {
+            return Some(rect.min);
+        }
+        // The rectangle is connected, so if some point is not covered, one of the uncovered
+        // points is next to a covered one, hence just outside of a diamond
+        diamonds
+            .iter()
+            .find_map(|diamond| diamond.uncovered_boundary_point(diamonds, rect))
+    }
+
+    pub fn covers(diamonds: &[Self], rect: &Rect<S>) -> bool {
+        Self::uncovered_point(diamonds, rect).is_none()
+    }
+
+    // Points of the rectangle which are in none of the diamonds, sorted, stopping after `limit`
+    // of them. The rotated coordinates are swept between the edges of the squares, where the
+    // coverage is constant
+    pub fn uncovered_points(diamonds: &[Self], rect: &Rect<S>, limit: usize) -> Vec<Point2<S>> {
+        if rect.is_empty() {
+            return vec![];
+        }
+        let (one, two) = (S::one(), S::one() + S::one());
+        let squares: Vec<_> = diamonds.iter().map(|d| d.to_rotated()).collect();
+        let (min, max) = (rect.min, rect.max);
+        let (u_min, u_max) = (min.x + min.y, max.x + max.y);
+        let Some(v_bounds) = Interval::new(min.x - max.y, max.x - min.y) else {
+            return vec![];
+        };
+
+        let mut edges: Vec<_> = squares
+            .iter()
+            .flat_map(|square| [square.min.x, square.max.x + one])
+            .filter(|&u| u_min < u && u <= u_max)
+            .chain([u_min, u_max + one])
+            .collect();
+        edges.sort();
+        edges.dedup();
+
+        let mut points = vec![];
+        'sweep: for slab in edges.windows(2) {
+            let (start, end) = (slab[0], slab[1] - one);
+            let covered: RangeSet<S> = squares
+                .iter()
+                .filter(|square| square.min.x <= start && end <= square.max.x)
+                .filter_map(|square| Interval::new(square.min.y, square.max.y))
+                .collect();
+            for gap in covered.gaps(v_bounds).intervals() {
+                // Values of u for which the gap crosses the rectangle
+                let mut u = start
+                    .max(two * min.x - gap.end())
+                    .max(gap.start() + two * min.y);
+                let u_end = end
+                    .min(gap.end() + two * max.y)
+                    .min(two * max.x - gap.start());
+                while u <= u_end {
+                    let v_start = gap.start().max(two * min.x - u).max(u - two * max.y);
+                    let v_end = gap.end().min(two * max.x - u).min(u - two * min.y);
+                    // Only points where u and v have the same parity are on the grid
+                    let mut v = match (v_start - u).is_odd() {
+                        true => v_start + one,
+                        false => v_start,
+                    };
+                    while v <= v_end {
+                        if points.len() == limit {
+                            break 'sweep;
+                        }
+                        points.extend(point2!(u, v).unrotate_45());
+                        v = v + two;
+                    }
+                    u = u + one;
+                }
+            }
+        }
+        points.sort();
+        points
+    }
+
+    fn uncovered_boundary_point(&self, diamonds: &[Self], rect: &Rect<S>) -> Option<Point2<S>> {
+        let outer = self.radius + S::one();
+        let center = self.center.rotate_45();
+        let u_edge = Interval::new(center.y - outer, center.y + outer)?;
+        let v_edge = Interval::new(center.x - outer, center.x + outer)?;
+
+        [
+            (RotatedAxis::U, center.x - outer, u_edge),
+            (RotatedAxis::U, center.x + outer, u_edge),
+            (RotatedAxis::V, center.y - outer, v_edge),
+            (RotatedAxis::V, center.y + outer, v_edge),
+        ]
+        .into_iter()
+        .find_map(|(axis, fixed, edge)| {
+            Self::uncovered_point_on_line(diamonds, rect, axis, fixed, edge)
+        })
+    }
+
+    // Scans the rotated line where `axis` equals `fixed`, restricted to `along`
+    fn uncovered_point_on_line(
+        diamonds: &[Self],
+        rect: &Rect<S>,
+        axis: RotatedAxis,
+        fixed: S,
+        along: Interval<S>,
+    ) -> Option<Point2<S>> {
+        let two = S::one() + S::one();
+        let x_bounds = Interval::new(two * rect.min.x - fixed, two * rect.max.x - fixed)?;
+        let y_bounds = match axis {
+            RotatedAxis::U => Interval::new(fixed - two * rect.max.y, fixed - two * rect.min.y)?,
+            RotatedAxis::V => Interval::new(fixed + two * rect.min.y, fixed + two * rect.max.y)?,
+        };
+        let allowed = along.intersection(&x_bounds)?.intersection(&y_bounds)?;
+
+        // Only one point out of two of the rotated line is on the grid: index them
+        let parity = fixed.mod_floor(&two);
+        let to_index = |interval: Interval<S>| {
+            Interval::new(
+                (interval.start() - parity).div_ceil(&two),
+                (interval.end() - parity).div_floor(&two),
+            )
+        };
+
+        let covered: RangeSet<S> = diamonds
+            .iter()
+            .map(|diamond| diamond.to_rotated())
+            .filter_map(|square| match axis {
+                RotatedAxis::U if square.min.x <= fixed && fixed <= square.max.x => {
+                    Interval::new(square.min.y, square.max.y)
+                }
+                RotatedAxis::V if square.min.y <= fixed && fixed <= square.max.y => {
+                    Interval::new(square.min.x, square.max.x)
+                }
+                _ => None,
+            })
+            .filter_map(to_index)
+            .collect();
+
+        let index = covered.first_gap(to_index(allowed)?)?;
+        let other = two * index + parity;
+        match axis {
+            RotatedAxis::U => point2!(fixed, other),
+            RotatedAxis::V => point2!(other, fixed),
+        }
+        .unrotate_45()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direction_turns() {
+        assert_eq!(Direction4::Up.turn_left(), Direction4::Left);
+        assert_eq!(Direction4::Left.turn_right(), Direction4::Up);
+        assert!(Direction4::Right.is_opposite(Direction4::Left));
+        assert_eq!(Direction8::UpRight.turn_around(), Direction8::DownLeft);
+        assert_eq!(Direction8::Up.rotate(-1), Direction8::UpLeft);
+        assert!(Direction8::UpLeft.is_diagonal());
+        assert!(!Direction8::Left.is_diagonal());
+    }
+
+    #[test]
+    fn test_direction_vectors() {
+        for direction in Direction8::ALL {
+            let vector: Vector2<i32> = direction.to_vector();
+            assert_eq!(Direction8::try_from(vector), Ok(direction));
+        }
+        assert_eq!(Direction4::try_from(point2!(1, 1)), Err(()));
+        assert_eq!("NE".parse(), Ok(Direction8::UpRight));
+        assert_eq!(Direction4::try_from('v'), Ok(Direction4::Down));
+    }
+
+    #[test]
+    fn test_checked_step() {
+        let bounds = Rect::new(point2!(0u8, 0), point2!(3, 3));
+        assert_eq!(point2!(0, 0).checked_step(Direction4::Left, &bounds), None);
+        assert_eq!(
+            point2!(0, 0).saturating_step(Direction8::UpLeft, &bounds),
+            point2!(0, 1)
+        );
+        assert_eq!(
+            point2!(2, 2).checked_step(Direction8::DownRight, &bounds),
+            Some(point2!(3, 1))
+        );
+        assert_eq!(
+            point2!(2, 2).checked_step_y_down(Direction8::DownRight, &bounds),
+            Some(point2!(3, 3))
+        );
+        assert_eq!(
+            point2!(1, 0).checked_step_y_down(Direction4::Up, &bounds),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diamond_rotation() {
+        let diamond = Diamond::new(point2!(3, -2), 4);
+        let square = diamond.to_rotated();
+        assert_eq!(square, Rect::new(point2!(-3, 1), point2!(5, 9)));
+        assert_eq!(Diamond::from_rotated(&square), Some(diamond));
+        assert_eq!(
+            point2!(3, -2).rotate_45().unrotate_45(),
+            Some(point2!(3, -2))
+        );
+    }
+
+    #[test]
+    fn test_diamond_outer_boundary() {
+        let diamond = Diamond::new(point2!(1, 1), 2);
+        let boundary: Vec<_> = diamond.outer_boundary().collect();
+        assert_eq!(boundary.len(), 12);
+        assert!(boundary.iter().all(|p| p.distance_1(&diamond.center) == 3));
+    }
+
+    #[test]
+    fn test_diamond_intersection() {
+        let a = Diamond::new(point2!(0, 0), 2);
+        let b = Diamond::new(point2!(4, 0), 2);
+        let c = Diamond::new(point2!(5, 0), 2);
+        assert!(a.intersects(&b));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Rect::new(point2!(2, 2), point2!(2, 2)))
+        );
+        assert!(!a.intersects(&c));
+        assert_eq!(a.intersection(&c), None);
+    }
+
+    #[test]
+    fn test_diamonds_cover_rect() {
+        let rect = Rect::new(point2!(0, 0), point2!(4, 4));
+        let diamonds = [
+            Diamond::new(point2!(0, 0), 4),
+            Diamond::new(point2!(4, 4), 2),
+        ];
+        let point = Diamond::uncovered_point(&diamonds, &rect).unwrap();
+        assert!(rect.contains(&point));
+        assert!(diamonds.iter().all(|d| !d.contains(&point)));
+
+        let diamonds = [
+            Diamond::new(point2!(0, 0), 4),
+            Diamond::new(point2!(4, 4), 3),
+        ];
+        assert!(Diamond::covers(&diamonds, &rect));
+
+        let far = [Diamond::new(point2!(10, 10), 2)];
+        assert_eq!(Diamond::uncovered_point(&far, &rect), Some(point2!(0, 0)));
+    }
+
+    #[test]
+    fn test_uncovered_points() {
+        let diamonds = [
+            Diamond::new(point2!(0, 0), 4),
+            Diamond::new(point2!(4, 4), 2),
+            Diamond::new(point2!(-3, 6), 1),
+            Diamond::new(point2!(7, -1), 3),
+        ];
+        for rect in [
+            Rect::new(point2!(0, 0), point2!(4, 4)),
+            Rect::new(point2!(-6, -5), point2!(9, 8)),
+            Rect::new(point2!(3, -2), point2!(3, 7)),
+            Rect::new(point2!(20, 20), point2!(21, 22)),
+        ] {
+            let expected: Vec<_> = (rect.min.x..=rect.max.x)
+                .flat_map(|x| (rect.min.y..=rect.max.y).map(move |y| point2!(x, y)))
+                .filter(|point| diamonds.iter().all(|d| !d.contains(point)))
+                .collect();
+            assert_eq!(
+                Diamond::uncovered_points(&diamonds, &rect, usize::MAX),
+                expected
+            );
+        }
+        let empty = Rect::new(point2!(1, 1), point2!(0, 0));
+        assert_eq!(Diamond::uncovered_points(&diamonds[..1], &empty, 1), vec![]);
+
+        let huge = Rect::new(point2!(0, 0), point2!(1_000_000, 1_000_000));
+        assert_eq!(Diamond::uncovered_points(&diamonds, &huge, 2).len(), 2);
+    }
+}
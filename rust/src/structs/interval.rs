@@ -0,0 +1,276 @@
+use num::Integer;
+
+// Inclusive range of integers
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Interval<S>
+where
+    S: Integer,
+{
+    start: S,
+    end: S,
+}
+
+impl<S> Interval<S>
+where
+    S: Integer + Copy,
+{
+    pub fn new(start: S, end: S) -> Option<Self> {
+        match start <= end {
+            true => Some(Self { start, end }),
+            false => None,
+        }
+    }
+
+    pub fn start(&self) -> S {
+        self.start
+    }
+
+    pub fn end(&self) -> S {
+        self.end
+    }
+
+    pub fn len(&self) -> S {
+        self.end - self.start + S::one()
+    }
+
+    pub fn contains(&self, value: S) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn includes(&self, other: &Self) -> bool {
+        self.start <= other.start && self.end >= other.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    // Overlapping or directly next to each other, so that their union is an interval
+    pub fn touches(&self, other: &Self) -> bool {
+        self.overlaps(other)
+            || (self.end < other.start && self.end + S::one() == other.start)
+            || (other.end < self.start && other.end + S::one() == self.start)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    // Smallest interval containing both
+    pub fn hull(&self, other: &Self) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+// Set of integers stored as sorted, disjoint and non-adjacent intervals
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RangeSet<S>
+where
+    S: Integer,
+{
+    intervals: Vec<Interval<S>>,
+}
+
+impl<S> Default for RangeSet<S>
+where
+    S: Integer,
+{
+    fn default() -> Self {
+        Self { intervals: vec![] }
+    }
+}
+
+impl<S> RangeSet<S>
+where
+    S: Integer + Copy,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval<S>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn insert(&mut self, interval: Interval<S>) {
+        let mut merged = interval;
+        let mut intervals = Vec::with_capacity(self.intervals.len() + 1);
+        for current in &self.intervals {
+            match current.touches(&merged) {
+                true => merged = merged.hull(current),
+                false => intervals.push(*current),
+            }
+        }
+        let position = intervals.partition_point(|i| i.start < merged.start);
+        intervals.insert(position, merged);
+        self.intervals = intervals;
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        other.intervals.iter().for_each(|i| result.insert(*i));
+        result
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+            if let Some(common) = a.intersection(&b) {
+                intervals.push(common);
+            }
+            match a.end < b.end {
+                true => i += 1,
+                false => j += 1,
+            }
+        }
+        Self { intervals }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut intervals = vec![];
+        for interval in &self.intervals {
+            let mut remaining = Some(*interval);
+            for removed in other.intervals.iter().filter(|i| i.overlaps(interval)) {
+                let Some(current) = remaining else {
+                    break;
+                };
+                if removed.start > current.start {
+                    intervals.push(Interval {
+                        start: current.start,
+                        end: removed.start - S::one(),
+                    });
+                }
+                remaining = match removed.end < current.end {
+                    true => Some(Interval {
+                        start: removed.end + S::one(),
+                        end: current.end,
+                    }),
+                    false => None,
+                };
+            }
+            intervals.extend(remaining);
+        }
+        Self { intervals }
+    }
+
+    // Number of integers in the set
+    pub fn len(&self) -> S {
+        self.intervals
+            .iter()
+            .fold(S::zero(), |acc, interval| acc + interval.len())
+    }
+
+    pub fn contains(&self, value: S) -> bool {
+        let position = self.intervals.partition_point(|i| i.end < value);
+        self.intervals
+            .get(position)
+            .is_some_and(|i| i.contains(value))
+    }
+
+    // Parts of `within` that are not in the set
+    pub fn gaps(&self, within: Interval<S>) -> Self {
+        Self::from(within).difference(self)
+    }
+
+    pub fn first_gap(&self, within: Interval<S>) -> Option<S> {
+        self.gaps(within).intervals.first().map(|i| i.start)
+    }
+}
+
+impl<S> From<Interval<S>> for RangeSet<S>
+where
+    S: Integer,
+{
+    fn from(value: Interval<S>) -> Self {
+        Self {
+            intervals: vec![value],
+        }
+    }
+}
+
+impl<S> FromIterator<Interval<S>> for RangeSet<S>
+where
+    S: Integer + Copy,
+{
+    fn from_iter<T: IntoIterator<Item = Interval<S>>>(iter: T) -> Self {
+        let mut intervals: Vec<_> = iter.into_iter().collect();
+        intervals.sort();
+
+        let mut result: Vec<Interval<S>> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match result.last_mut() {
+                Some(last) if last.touches(&interval) => *last = last.hull(&interval),
+                _ => result.push(interval),
+            }
+        }
+        Self { intervals: result }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(start: i32, end: i32) -> Interval<i32> {
+        Interval::new(start, end).unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(Interval::new(5, 1), None);
+        let interval = interval(-2, 3);
+        assert_eq!(
+            (interval.start(), interval.end(), interval.len()),
+            (-2, 3, 6)
+        );
+    }
+
+    #[test]
+    fn test_insert_merges_intervals() {
+        let mut set = RangeSet::new();
+        assert!(set.is_empty());
+        set.insert(interval(5, 7));
+        set.insert(interval(0, 2));
+        set.insert(interval(3, 4));
+        set.insert(interval(10, 12));
+
+        assert_eq!(set.intervals(), &[interval(0, 7), interval(10, 12)]);
+        assert_eq!(set.len(), 11);
+        assert!(set.contains(11));
+        assert!(!set.contains(8));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: RangeSet<_> = [interval(0, 10), interval(20, 30)].into_iter().collect();
+        let b: RangeSet<_> = [interval(5, 25)].into_iter().collect();
+
+        assert_eq!(a.union(&b).intervals(), &[interval(0, 30)]);
+        assert_eq!(
+            a.intersection(&b).intervals(),
+            &[interval(5, 10), interval(20, 25)]
+        );
+        assert_eq!(
+            a.difference(&b).intervals(),
+            &[interval(0, 4), interval(26, 30)]
+        );
+    }
+
+    #[test]
+    fn test_first_gap() {
+        let set: RangeSet<_> = [interval(-3, 4), interval(6, 20)].into_iter().collect();
+
+        assert_eq!(set.first_gap(interval(0, 20)), Some(5));
+        assert_eq!(set.first_gap(interval(6, 20)), None);
+        assert_eq!(set.first_gap(interval(-10, 20)), Some(-10));
+    }
+}
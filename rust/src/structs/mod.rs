@@ -1 +1,6 @@
+// Building blocks shared by the puzzles, no single day uses all of their API
+#![allow(dead_code)]
+
 pub mod geometry;
+pub mod interval;
+pub mod point_n;
@@ -0,0 +1,318 @@
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+use num::{Integer, Signed};
+
+use super::geometry::{Point2, Point3};
+
+// Point with an arbitrary number of dimensions
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct PointN<S, const N: usize>
+where
+    S: Integer,
+{
+    pub coords: [S; N],
+}
+
+pub type VectorN<S, const N: usize> = PointN<S, N>;
+
+impl<S, const N: usize> PointN<S, N>
+where
+    S: Integer + Copy,
+{
+    pub fn new(coords: [S; N]) -> Self {
+        Self { coords }
+    }
+
+    pub fn zero() -> Self {
+        Self {
+            coords: [S::zero(); N],
+        }
+    }
+
+    pub fn unit(axis: usize) -> Self {
+        let mut point = Self::zero();
+        point.coords[axis] = S::one();
+        point
+    }
+
+    fn zip_with(self, rhs: Self, f: impl Fn(S, S) -> S) -> Self {
+        let mut coords = self.coords;
+        coords
+            .iter_mut()
+            .zip(rhs.coords)
+            .for_each(|(lhs, rhs)| *lhs = f(*lhs, rhs));
+        Self { coords }
+    }
+
+    fn map(self, f: impl Fn(S) -> S) -> Self {
+        Self {
+            coords: self.coords.map(f),
+        }
+    }
+}
+
+impl<S, const N: usize> PointN<S, N>
+where
+    S: Integer + Copy + Signed,
+{
+    pub fn norm_1(&self) -> S {
+        self.coords.iter().fold(S::zero(), |acc, c| acc + c.abs())
+    }
+
+    pub fn norm_inf(&self) -> S {
+        self.coords
+            .iter()
+            .fold(S::zero(), |acc, c| acc.max(c.abs()))
+    }
+
+    pub fn distance_1(&self, rhs: &Self) -> S {
+        (*self - *rhs).norm_1()
+    }
+
+    pub fn distance_inf(&self, rhs: &Self) -> S {
+        (*self - *rhs).norm_inf()
+    }
+
+    // The 3^N - 1 points at a L∞ distance of one
+    pub fn neighbours(self) -> impl Iterator<Item = Self> {
+        let count = 3usize.pow(N as u32);
+        (0..count)
+            .filter(move |&i| i != count / 2)
+            .map(move |mut i| {
+                let mut offset = Self::zero();
+                offset.coords.iter_mut().for_each(|c| {
+                    *c = match i % 3 {
+                        0 => -S::one(),
+                        1 => S::zero(),
+                        _ => S::one(),
+                    };
+                    i /= 3;
+                });
+                self + offset
+            })
+    }
+
+    // The 2N points at a L1 distance of one
+    pub fn orthogonal_neighbours(self) -> impl Iterator<Item = Self> {
+        (0..N).flat_map(move |axis| [self - Self::unit(axis), self + Self::unit(axis)])
+    }
+}
+
+macro_rules! impl_pointn_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:tt) => {
+        impl<S, const N: usize> $trait for PointN<S, N>
+        where
+            S: Integer + Copy,
+        {
+            type Output = Self;
+
+            fn $method(self, rhs: Self) -> Self::Output {
+                self.zip_with(rhs, |a, b| a $op b)
+            }
+        }
+
+        impl<S, const N: usize> $assign_trait for PointN<S, N>
+        where
+            S: Integer + Copy,
+        {
+            fn $assign_method(&mut self, rhs: Self) {
+                *self = self.zip_with(rhs, |a, b| a $op b);
+            }
+        }
+    };
+}
+
+macro_rules! impl_pointn_scalar_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:tt) => {
+        impl<S, const N: usize> $trait<S> for PointN<S, N>
+        where
+            S: Integer + Copy,
+        {
+            type Output = Self;
+
+            fn $method(self, rhs: S) -> Self::Output {
+                self.map(|a| a $op rhs)
+            }
+        }
+
+        impl<S, const N: usize> $assign_trait<S> for PointN<S, N>
+        where
+            S: Integer + Copy,
+        {
+            fn $assign_method(&mut self, rhs: S) {
+                *self = self.map(|a| a $op rhs);
+            }
+        }
+    };
+}
+
+impl_pointn_op!(Add, add, AddAssign, add_assign, +);
+impl_pointn_op!(Sub, sub, SubAssign, sub_assign, -);
+impl_pointn_scalar_op!(Mul, mul, MulAssign, mul_assign, *);
+impl_pointn_scalar_op!(Div, div, DivAssign, div_assign, /);
+
+impl<S, const N: usize> Neg for PointN<S, N>
+where
+    S: Integer + Copy + Signed,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.map(|a| -a)
+    }
+}
+
+impl<S, const N: usize> Index<usize> for PointN<S, N>
+where
+    S: Integer,
+{
+    type Output = S;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.coords[index]
+    }
+}
+
+impl<S, const N: usize> IndexMut<usize> for PointN<S, N>
+where
+    S: Integer,
+{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.coords[index]
+    }
+}
+
+impl<S, const N: usize> From<[S; N]> for PointN<S, N>
+where
+    S: Integer,
+{
+    fn from(coords: [S; N]) -> Self {
+        Self { coords }
+    }
+}
+
+impl<S> From<Point2<S>> for PointN<S, 2>
+where
+    S: Integer,
+{
+    fn from(value: Point2<S>) -> Self {
+        Self {
+            coords: [value.x, value.y],
+        }
+    }
+}
+
+impl<S> From<PointN<S, 2>> for Point2<S>
+where
+    S: Integer,
+{
+    fn from(value: PointN<S, 2>) -> Self {
+        let [x, y] = value.coords;
+        Self { x, y }
+    }
+}
+
+impl<S> From<Point3<S>> for PointN<S, 3>
+where
+    S: Integer,
+{
+    fn from(value: Point3<S>) -> Self {
+        Self {
+            coords: [value.x, value.y, value.z],
+        }
+    }
+}
+
+impl<S> From<PointN<S, 3>> for Point3<S>
+where
+    S: Integer,
+{
+    fn from(value: PointN<S, 3>) -> Self {
+        let [x, y, z] = value.coords;
+        Self { x, y, z }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+    use crate::structs::geometry::point2;
+
+    #[test]
+    fn test_operators() {
+        let a = PointN::new([1, -2, 3, 0]);
+        let b = PointN::new([2, 2, -1, 5]);
+
+        assert_eq!(a + b, PointN::new([3, 0, 2, 5]));
+        assert_eq!(a - b, PointN::new([-1, -4, 4, -5]));
+        assert_eq!(-a * 2, PointN::new([-2, 4, -6, 0]));
+        assert_eq!(a.distance_1(&b), 14);
+        assert_eq!(a.distance_inf(&b), 5);
+        assert_eq!(b[3], 5);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let point: PointN<i32, 2> = point2!(3, 4).into();
+        assert_eq!(point, PointN::new([3, 4]));
+        assert_eq!(Point2::from(point), point2!(3, 4));
+
+        let point3 = Point3 { x: 3, y: 4, z: 5 };
+        let point: PointN<i32, 3> = point3.into();
+        assert_eq!(point, PointN::new([3, 4, 5]));
+        assert_eq!(Point3::from(point), point3);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let origin = PointN::<i8, 3>::zero();
+        let neighbours: HashSet<_> = origin.neighbours().collect();
+        assert_eq!(neighbours.len(), 26);
+        assert!(neighbours.iter().all(|p| p.norm_inf() == 1));
+        assert_eq!(origin.orthogonal_neighbours().count(), 6);
+    }
+
+    fn conway_cubes<const N: usize>(input: &str, cycles: usize) -> usize {
+        let mut active: HashSet<PointN<i32, N>> = input
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| {
+                        let mut point = PointN::zero();
+                        point[0] = x as i32;
+                        point[1] = y as i32;
+                        point
+                    })
+            })
+            .collect();
+
+        for _ in 0..cycles {
+            let mut counts: HashMap<PointN<i32, N>, usize> = HashMap::new();
+            active
+                .iter()
+                .flat_map(|p| p.neighbours())
+                .for_each(|p| *counts.entry(p).or_default() += 1);
+            active = counts
+                .into_iter()
+                .filter(|(p, count)| *count == 3 || (*count == 2 && active.contains(p)))
+                .map(|(p, _)| p)
+                .collect();
+        }
+
+        active.len()
+    }
+
+    #[test]
+    fn test_conway_cubes() {
+        let input = ".#.\n..#\n###";
+        assert_eq!(conway_cubes::<3>(input, 6), 112);
+        assert_eq!(conway_cubes::<4>(input, 6), 848);
+    }
+}
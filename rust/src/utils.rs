@@ -1,22 +1,57 @@
+use std::{
+    error::Error,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
 #[allow(dead_code)]
 pub enum InputKind {
     Test,
     Run,
 }
 
+pub type Answer = Result<String, Box<dyn Error>>;
+
+// Puzzles return either a plain answer, or a result when their input can be invalid
+pub trait IntoAnswer {
+    fn into_answer(self) -> Answer;
+}
+
+impl IntoAnswer for String {
+    fn into_answer(self) -> Answer {
+        Ok(self)
+    }
+}
+
+impl<E: Error + 'static> IntoAnswer for Result<String, E> {
+    fn into_answer(self) -> Answer {
+        self.map_err(|error| error.into())
+    }
+}
+
 pub struct Day {
     pub day: u8,
-    pub puzzle_1: fn(&str) -> String,
-    pub puzzle_2: fn(&str) -> String,
+    pub puzzle_1: fn(&str) -> Answer,
+    pub puzzle_2: fn(&str) -> Answer,
 }
 
 macro_rules! get_day {
     ($n: tt, $mod:tt) => {
         Day {
             day: $n,
-            puzzle_1: $mod::puzzle_1,
-            puzzle_2: $mod::puzzle_2,
+            puzzle_1: |input| $crate::utils::IntoAnswer::into_answer($mod::puzzle_1(input)),
+            puzzle_2: |input| $crate::utils::IntoAnswer::into_answer($mod::puzzle_2(input)),
         }
     };
 }
 pub(crate) use get_day;
+
+// Days may print details about how they found their answer in verbose mode
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}